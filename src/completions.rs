@@ -0,0 +1,258 @@
+//! `completion/complete`: argument suggestions from live workspace data.
+//!
+//! Project names, tags, snapshot names and local branches are completed for
+//! prompts, resource templates and tools. MCP only defines `ref/prompt` and
+//! `ref/resource`; `ref/tool` is accepted as well so clients can complete tool
+//! arguments, which is where mistyped project names usually end up.
+
+use super::resources::{snapshot_files, SNAPSHOT_PREFIX};
+use super::{McpServer, RpcError};
+use anyhow::Result;
+use std::collections::BTreeSet;
+
+/// Most values returned in one completion, as required by the spec.
+const MAX_COMPLETIONS: usize = 100;
+
+/// What an argument is completed from.
+#[derive(Debug, PartialEq)]
+enum Source {
+    Projects,
+    Tags,
+    Snapshots,
+    Branches,
+}
+
+impl McpServer {
+    pub(crate) fn handle_complete(&self, params: &serde_json::Value) -> Result<serde_json::Value> {
+        let reference = params
+            .get("ref")
+            .ok_or_else(|| RpcError::new(-32602, "Missing 'ref' parameter"))?;
+        let argument = params
+            .get("argument")
+            .and_then(|a| a.get("name"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| RpcError::new(-32602, "Missing 'argument.name' parameter"))?;
+        let value = params
+            .get("argument")
+            .and_then(|a| a.get("value"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        // Arguments the client has already filled in, e.g. the tag to narrow
+        // branch suggestions down to
+        let context = params
+            .get("context")
+            .and_then(|c| c.get("arguments"))
+            .cloned()
+            .unwrap_or_default();
+
+        let candidates = match completion_source(reference, argument)? {
+            Some(source) => self.completion_candidates(source, &context),
+            None => Vec::new(),
+        };
+
+        let matches = rank_matches(candidates, value);
+        let total = matches.len();
+        let values: Vec<String> = matches.into_iter().take(MAX_COMPLETIONS).collect();
+
+        Ok(serde_json::json!({
+            "completion": {
+                "values": values,
+                "total": total,
+                "hasMore": total > MAX_COMPLETIONS,
+            }
+        }))
+    }
+
+    fn completion_candidates(&self, source: Source, context: &serde_json::Value) -> Vec<String> {
+        let meta_dir = match self.meta_dir.as_ref() {
+            Some(meta_dir) => meta_dir,
+            None => return Vec::new(),
+        };
+        let projects = self.load_projects(meta_dir).unwrap_or_default();
+
+        match source {
+            Source::Projects => projects.into_iter().map(|p| p.name).collect(),
+            Source::Tags => projects
+                .into_iter()
+                .flat_map(|p| p.tags)
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+            Source::Snapshots => snapshot_files(&self.snapshots_dir(meta_dir))
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect(),
+            Source::Branches => {
+                let tag = context.get("tag").and_then(|v| v.as_str());
+                projects
+                    .iter()
+                    .filter(|p| tag.is_none_or(|tag| p.tags.iter().any(|t| t == tag)))
+                    .filter_map(|p| {
+                        self.git_output(
+                            &meta_dir.join(&p.path),
+                            &["branch", "--format=%(refname:short)"],
+                        )
+                        .ok()
+                    })
+                    .flat_map(|out| out.lines().map(str::to_string).collect::<Vec<_>>())
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Decide where suggestions for `argument` of `reference` come from, or `None`
+/// when the argument is not one we can complete.
+fn completion_source(reference: &serde_json::Value, argument: &str) -> Result<Option<Source>> {
+    let ref_type = reference.get("type").and_then(|v| v.as_str());
+    let ref_name = match ref_type {
+        Some("ref/prompt") | Some("ref/tool") => reference.get("name"),
+        Some("ref/resource") => reference.get("uri"),
+        _ => {
+            return Err(RpcError::new(
+                -32602,
+                format!(
+                    "Unsupported completion ref type: {}",
+                    ref_type.unwrap_or("none")
+                ),
+            )
+            .into())
+        }
+    }
+    .and_then(|v| v.as_str())
+    .unwrap_or("");
+
+    Ok(match (ref_type, ref_name, argument) {
+        (_, _, "project") => Some(Source::Projects),
+        (_, _, "tag") => Some(Source::Tags),
+        (Some("ref/tool"), "meta_snapshot_restore", "name") => Some(Source::Snapshots),
+        (Some("ref/resource"), uri, "name") if uri.starts_with(SNAPSHOT_PREFIX) => {
+            Some(Source::Snapshots)
+        }
+        (Some("ref/tool"), "meta_git_checkout", "branch") => Some(Source::Branches),
+        _ => None,
+    })
+}
+
+/// Keep candidates containing `value` (case-insensitively), prefix matches
+/// first, so a mistyped middle of a name still turns up suggestions.
+fn rank_matches(candidates: Vec<String>, value: &str) -> Vec<String> {
+    let needle = value.to_lowercase();
+    let (mut prefixed, mut contained): (Vec<String>, Vec<String>) = candidates
+        .into_iter()
+        .filter(|c| c.to_lowercase().contains(&needle))
+        .partition(|c| c.to_lowercase().starts_with(&needle));
+    prefixed.sort();
+    contained.sort();
+    prefixed.extend(contained);
+    prefixed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Outgoing;
+
+    fn server() -> (McpServer, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            tmp.path().join(".meta"),
+            r#"{"projects": {
+                "api": {"repo": "x", "tags": ["backend"]},
+                "web-app": {"repo": "y", "tags": ["frontend"]},
+                "shared-api": "z"
+            }}"#,
+        )
+        .unwrap();
+        std::fs::create_dir_all(tmp.path().join(".meta-snapshots")).unwrap();
+        std::fs::write(tmp.path().join(".meta-snapshots/pre-release.json"), "{}").unwrap();
+        let server = McpServer::with_meta_dir(
+            Some(tmp.path().to_path_buf()),
+            Outgoing::new(Box::new(std::io::sink())),
+        );
+        (server, tmp)
+    }
+
+    fn complete(
+        server: &McpServer,
+        reference: serde_json::Value,
+        name: &str,
+        value: &str,
+    ) -> Vec<String> {
+        let result = server
+            .handle_complete(&serde_json::json!({
+                "ref": reference,
+                "argument": {"name": name, "value": value}
+            }))
+            .unwrap();
+        serde_json::from_value(result["completion"]["values"].clone()).unwrap()
+    }
+
+    #[test]
+    fn test_complete_project_names() {
+        let (server, _tmp) = server();
+        let reference = serde_json::json!({"type": "ref/prompt", "name": "review_changes"});
+
+        assert_eq!(
+            complete(&server, reference.clone(), "project", "web"),
+            ["web-app"]
+        );
+        // Prefix matches come before substring matches
+        assert_eq!(
+            complete(&server, reference, "project", "API"),
+            ["api", "shared-api"]
+        );
+    }
+
+    #[test]
+    fn test_complete_tags_and_snapshots() {
+        let (server, _tmp) = server();
+
+        let tags = complete(
+            &server,
+            serde_json::json!({"type": "ref/tool", "name": "meta_run_tests"}),
+            "tag",
+            "",
+        );
+        assert_eq!(tags, ["backend", "frontend"]);
+
+        let snapshots = complete(
+            &server,
+            serde_json::json!({"type": "ref/tool", "name": "meta_snapshot_restore"}),
+            "name",
+            "pre",
+        );
+        assert_eq!(snapshots, ["pre-release"]);
+
+        let from_template = complete(
+            &server,
+            serde_json::json!({"type": "ref/resource", "uri": "meta://snapshot/{name}"}),
+            "name",
+            "",
+        );
+        assert_eq!(from_template, ["pre-release"]);
+    }
+
+    #[test]
+    fn test_complete_unknown_argument_and_ref() {
+        let (server, _tmp) = server();
+
+        let values = complete(
+            &server,
+            serde_json::json!({"type": "ref/tool", "name": "meta_git_commit"}),
+            "message",
+            "",
+        );
+        assert!(values.is_empty());
+
+        let err = server
+            .handle_complete(&serde_json::json!({
+                "ref": {"type": "ref/unknown"},
+                "argument": {"name": "project", "value": ""}
+            }))
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<RpcError>().unwrap().code, -32602);
+    }
+}
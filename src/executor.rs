@@ -0,0 +1,463 @@
+//! Bounded-concurrency execution of per-project work.
+//!
+//! Tools that walk every project run the projects on a small pool of scoped
+//! threads instead of one after another, so a workspace's test suites take as
+//! long as the slowest few rather than their sum. Results are still returned in
+//! project order, and progress is reported as each project finishes.
+//!
+//! The git, exec and build tools use the same loop to run their command in
+//! each project themselves, the way `meta` does, rather than through the
+//! `meta` binary. They work without it on `PATH`, and each project's outcome
+//! comes back as its own result instead of one block of scraped output. Like
+//! `meta`, they also cover the workspace's own repository, as project `.`.
+//! meta_cli offers these commands only as part of its CLI, not as library
+//! calls, which is why they are run here rather than called into.
+//!
+//! `meta_exec` therefore runs its command as a program in each project; it no
+//! longer reaches meta subcommands or plugins, which have tools of their own.
+//!
+//! Calls to tools that change projects hold the projects they apply to until
+//! they finish, across every session of the process, so two clients of one
+//! HTTP or socket server cannot interleave git operations in the same
+//! repository. Calls on different projects still run side by side.
+
+use super::process::failed_project;
+use super::McpServer;
+use anyhow::Result;
+use meta_core::config::ProjectInfo;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+/// Upper bound for the default, so a many-core machine does not start dozens
+/// of compilers at once.
+const MAX_DEFAULT_CONCURRENCY: usize = 8;
+
+/// How often a call waiting for its projects checks for cancellation.
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Directories of the projects held by running tool calls, in any session.
+static BUSY_PROJECTS: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+
+/// Signalled whenever projects are released.
+static PROJECTS_RELEASED: Condvar = Condvar::new();
+
+/// Projects held by one tool call, released when dropped.
+pub(crate) struct ProjectLock {
+    paths: Vec<PathBuf>,
+}
+
+impl Drop for ProjectLock {
+    fn drop(&mut self) {
+        if let Ok(mut busy) = BUSY_PROJECTS.lock() {
+            for path in &self.paths {
+                busy.remove(path);
+            }
+        }
+        PROJECTS_RELEASED.notify_all();
+    }
+}
+
+/// Schema of the `concurrency` argument shared by the per-project tools.
+pub(crate) fn concurrency_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "integer",
+        "minimum": 1,
+        "description": "How many projects to work on at once (default: the number of CPUs, at most 8)"
+    })
+}
+
+/// Schema of one project's result from `run_in_project`.
+pub(crate) fn command_result_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "project": { "type": "string" },
+            "success": { "type": "boolean" },
+            "exit_code": { "type": ["integer", "null"] },
+            "stdout": { "type": "string" },
+            "stderr": { "type": "string" },
+            "error": { "type": "string" },
+            "timed_out": { "type": "boolean" },
+            "skipped": { "type": "string", "description": "Why nothing was run" }
+        },
+        "required": ["project", "success"]
+    })
+}
+
+/// The projects a call applies to: the one its `project` argument names, the
+/// ones tagged with its `tag` argument, or else all of them.
+pub(crate) fn selected_projects<'a>(
+    projects: &'a [ProjectInfo],
+    args: &serde_json::Value,
+) -> Result<Vec<&'a ProjectInfo>> {
+    if let Some(name) = args.get("project").and_then(|v| v.as_str()) {
+        let project = projects
+            .iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Project '{name}' not found"))?;
+        return Ok(vec![project]);
+    }
+    Ok(match args.get("tag").and_then(|v| v.as_str()) {
+        Some(tag) => projects
+            .iter()
+            .filter(|p| p.tags.iter().any(|t| t == tag))
+            .collect(),
+        None => projects.iter().collect(),
+    })
+}
+
+/// Projects worked on at once when a call does not say.
+pub(crate) fn default_concurrency() -> usize {
+    std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(MAX_DEFAULT_CONCURRENCY)
+}
+
+impl McpServer {
+    /// The `concurrency` argument of a call, or else the workspace's setting,
+    /// or else the default.
+    pub(crate) fn concurrency(&self, args: &serde_json::Value) -> usize {
+        args.get("concurrency")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .or_else(|| self.server_config().concurrency)
+            .map_or_else(default_concurrency, |n| n.max(1))
+    }
+
+    /// The directories of the projects a call with `args` applies to: those
+    /// its `project` or `tag` argument selects, or else all of them.
+    pub(crate) fn selected_project_paths(&self, args: &serde_json::Value) -> Vec<PathBuf> {
+        let Some(meta_dir) = &self.meta_dir else {
+            return Vec::new();
+        };
+        let Ok(projects) = self.load_projects_with_root(meta_dir) else {
+            return vec![meta_dir.clone()];
+        };
+        selected_projects(&projects, args)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|project| meta_dir.join(&project.path))
+            .collect()
+    }
+
+    /// Hold `paths` for this call once no other call holds any of them. They
+    /// are taken all at once, so calls waiting on each other cannot deadlock.
+    pub(crate) fn lock_projects(&self, paths: Vec<PathBuf>) -> Result<ProjectLock> {
+        let mut busy = BUSY_PROJECTS
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Project locks poisoned"))?;
+        while paths.iter().any(|path| busy.contains(path)) {
+            if self.is_cancelled() {
+                anyhow::bail!("Request cancelled");
+            }
+            busy = PROJECTS_RELEASED
+                .wait_timeout(busy, LOCK_POLL_INTERVAL)
+                .map_err(|_| anyhow::anyhow!("Project locks poisoned"))?
+                .0;
+        }
+        busy.extend(paths.iter().cloned());
+        Ok(ProjectLock { paths })
+    }
+
+    /// Run `command` (program, then its arguments) in `project` and describe
+    /// how it went.
+    pub(crate) fn run_in_project(
+        &self,
+        meta_dir: &Path,
+        project: &ProjectInfo,
+        command: &[&str],
+    ) -> serde_json::Value {
+        let project_path = meta_dir.join(&project.path);
+        if !project_path.exists() {
+            return serde_json::json!({
+                "project": project.name,
+                "success": false,
+                "error": "Path does not exist"
+            });
+        }
+        let (program, command_args) = match command.split_first() {
+            Some(split) => split,
+            None => {
+                return serde_json::json!({
+                    "project": project.name,
+                    "success": false,
+                    "error": "Empty command"
+                })
+            }
+        };
+
+        let output = self.run_command(
+            Command::new(program)
+                .args(command_args)
+                .current_dir(&project_path),
+        );
+        match output {
+            Ok(out) => serde_json::json!({
+                "project": project.name,
+                "success": out.status.success(),
+                "exit_code": out.status.code(),
+                "stdout": String::from_utf8_lossy(&out.stdout).to_string(),
+                "stderr": String::from_utf8_lossy(&out.stderr).to_string()
+            }),
+            Err(e) => {
+                let mut result = failed_project(&project.name, &e);
+                result["success"] = false.into();
+                result
+            }
+        }
+    }
+
+    /// Run `job` for every project, at most `concurrency` at a time, and
+    /// return the results in the order of `projects`.
+    pub(crate) fn for_each_project<R, F>(
+        &self,
+        projects: &[&ProjectInfo],
+        concurrency: usize,
+        job: F,
+    ) -> Vec<R>
+    where
+        R: Send,
+        F: Fn(&ProjectInfo) -> R + Sync,
+    {
+        let progress = self.progress(projects.len());
+        let next = AtomicUsize::new(0);
+        let results: Vec<Mutex<Option<R>>> = projects.iter().map(|_| Mutex::new(None)).collect();
+
+        std::thread::scope(|scope| {
+            for _ in 0..concurrency.clamp(1, projects.len().max(1)) {
+                scope.spawn(|| loop {
+                    let i = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(project) = projects.get(i) else {
+                        break;
+                    };
+                    let result = job(project);
+                    if let Ok(mut slot) = results[i].lock() {
+                        *slot = Some(result);
+                    }
+                    progress.project_finished(&project.name);
+                });
+            }
+        });
+        progress.finish();
+
+        results
+            .into_iter()
+            .filter_map(|slot| slot.into_inner().ok().flatten())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Outgoing;
+    use std::sync::Arc;
+
+    fn projects(names: &[&str]) -> Vec<ProjectInfo> {
+        names
+            .iter()
+            .map(|name| ProjectInfo {
+                name: name.to_string(),
+                path: name.to_string(),
+                repo: None,
+                tags: Vec::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_results_keep_project_order() {
+        let server = McpServer::with_meta_dir(None, Outgoing::new(Box::new(std::io::sink())));
+        let projects = projects(&["slow", "fast", "medium"]);
+        let refs: Vec<&ProjectInfo> = projects.iter().collect();
+
+        let names = server.for_each_project(&refs, 3, |project| {
+            let delay = match project.name.as_str() {
+                "slow" => 60,
+                "medium" => 30,
+                _ => 0,
+            };
+            std::thread::sleep(Duration::from_millis(delay));
+            project.name.clone()
+        });
+        assert_eq!(names, ["slow", "fast", "medium"]);
+    }
+
+    #[test]
+    fn test_concurrency_is_bounded() {
+        let server = McpServer::with_meta_dir(None, Outgoing::new(Box::new(std::io::sink())));
+        let projects = projects(&["a", "b", "c", "d"]);
+        let refs: Vec<&ProjectInfo> = projects.iter().collect();
+        let running = AtomicUsize::new(0);
+        let most = AtomicUsize::new(0);
+
+        server.for_each_project(&refs, 2, |_| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            most.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(50));
+            running.fetch_sub(1, Ordering::SeqCst);
+        });
+
+        assert_eq!(most.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_calls_take_turns_on_shared_projects() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (api, web) = (tmp.path().join("api"), tmp.path().join("web"));
+        let server = McpServer::with_meta_dir(None, Outgoing::new(Box::new(std::io::sink())));
+
+        let held = server.lock_projects(vec![api.clone()]).unwrap();
+        // Other projects are free
+        drop(server.lock_projects(vec![web.clone()]).unwrap());
+
+        let (acquired, waiting) = std::sync::mpsc::channel();
+        let other = server.clone();
+        let paths = vec![web, api];
+        let waiter = std::thread::spawn(move || {
+            let _lock = other.lock_projects(paths).unwrap();
+            acquired.send(()).unwrap();
+        });
+        assert!(waiting.recv_timeout(Duration::from_millis(200)).is_err());
+
+        drop(held);
+        waiting.recv().unwrap();
+        waiter.join().unwrap();
+    }
+
+    #[test]
+    fn test_cancelled_call_stops_waiting_for_projects() {
+        let tmp = tempfile::tempdir().unwrap();
+        let server = McpServer::with_meta_dir(None, Outgoing::new(Box::new(std::io::sink())));
+        let _held = server
+            .lock_projects(vec![tmp.path().to_path_buf()])
+            .unwrap();
+
+        let context = Arc::new(crate::RequestContext::default());
+        context.cancel();
+        let cancelled = McpServer {
+            request: Some(context),
+            ..server.clone()
+        };
+        let err = cancelled
+            .lock_projects(vec![tmp.path().to_path_buf()])
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("cancelled"));
+    }
+
+    #[test]
+    fn test_git_tools_run_in_process() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join(".meta"), r#"{"projects": {"api": "x"}}"#).unwrap();
+        let api = tmp.path().join("api");
+        std::fs::create_dir_all(&api).unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(args)
+                .current_dir(&api)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success());
+        };
+        git(&["init", "-q"]);
+        git(&["config", "user.name", "Test"]);
+        git(&["config", "user.email", "test@example.com"]);
+        std::fs::write(api.join("README.md"), "api").unwrap();
+
+        let server = McpServer::with_meta_dir(
+            Some(tmp.path().to_path_buf()),
+            Outgoing::new(Box::new(std::io::sink())),
+        );
+        let call = |name: &str, args: serde_json::Value| {
+            server.call_tool(name, &args).unwrap().into_value()
+        };
+
+        let status = call("meta_git_status", serde_json::json!({}));
+        assert_eq!(status[0]["project"], "api");
+        assert_eq!(status[0]["dirty"], true);
+        assert_eq!(status[0]["changes"], serde_json::json!(["?? README.md"]));
+
+        let added = call("meta_git_add", serde_json::json!({}));
+        assert_eq!(added[0]["success"], true);
+        let committed = call(
+            "meta_git_commit",
+            serde_json::json!({"message": "Add README"}),
+        );
+        assert_eq!(committed[0]["success"], true);
+        assert_eq!(committed[0]["exit_code"], 0);
+
+        // Nothing left to commit, so the project is skipped
+        let committed = call("meta_git_commit", serde_json::json!({"message": "Again"}));
+        assert_eq!(
+            committed,
+            serde_json::json!([
+                {"project": "api", "success": true, "skipped": "nothing staged"}
+            ])
+        );
+        let status = call("meta_git_status", serde_json::json!({"project": "api"}));
+        assert_eq!(status[0]["dirty"], false);
+        assert!(server
+            .call_tool("meta_git_status", &serde_json::json!({"project": "web"}))
+            .is_err());
+    }
+
+    #[test]
+    fn test_git_tools_cover_the_root_repository() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join(".meta"), r#"{"projects": {"api": "x"}}"#).unwrap();
+        std::fs::write(tmp.path().join(".gitignore"), "api/\n").unwrap();
+        std::fs::create_dir_all(tmp.path().join("api")).unwrap();
+        for dir in [tmp.path().to_path_buf(), tmp.path().join("api")] {
+            let status = Command::new("git")
+                .args(["init", "-q"])
+                .current_dir(&dir)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+
+        let server = McpServer::with_meta_dir(
+            Some(tmp.path().to_path_buf()),
+            Outgoing::new(Box::new(std::io::sink())),
+        );
+        let status = server
+            .call_tool("meta_git_status", &serde_json::json!({}))
+            .unwrap()
+            .into_value();
+        assert_eq!(status[0]["project"], ".");
+        assert_eq!(
+            status[0]["changes"],
+            serde_json::json!(["?? .gitignore", "?? .meta"])
+        );
+        assert_eq!(status[1]["project"], "api");
+
+        let status = server
+            .call_tool("meta_git_status", &serde_json::json!({"project": "."}))
+            .unwrap()
+            .into_value();
+        assert_eq!(status.as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_concurrency_argument() {
+        let server = McpServer::with_meta_dir(None, Outgoing::new(Box::new(std::io::sink())));
+        assert_eq!(
+            server.concurrency(&serde_json::json!({"concurrency": 3})),
+            3
+        );
+        assert_eq!(
+            server.concurrency(&serde_json::json!({"concurrency": 0})),
+            1
+        );
+        assert_eq!(
+            server.concurrency(&serde_json::json!({})),
+            default_concurrency()
+        );
+    }
+}
@@ -0,0 +1,886 @@
+//! Streamable HTTP transport, selected with `--http ADDR`.
+//!
+//! A single endpoint, `/mcp`, lets one long-lived process serve several
+//! clients at once:
+//!
+//! - `POST` carries one JSON-RPC message. Notifications get `202 Accepted`;
+//!   requests are answered on a server-sent event stream carrying the
+//!   notifications the request emits followed by its response, or with plain
+//!   JSON when the client does not accept `text/event-stream`.
+//! - `GET` opens the session's stream for server-initiated messages such as
+//!   `notifications/resources/updated`.
+//! - `DELETE` ends the session.
+//!
+//! Every `initialize` starts a session with its own `McpServer` state
+//! (protocol revision, subscriptions, log level, in-flight requests),
+//! identified by the `Mcp-Session-Id` header. Sessions that see no request for
+//! `SESSION_IDLE_TIMEOUT` are ended as if deleted; their client gets `404` and
+//! has to initialize again.
+//!
+//! There is no authentication, and the tools run commands, so only loopback
+//! addresses are served unless `--allow-remote` is given.
+
+use super::plugins::SharedPlugins;
+use super::{Incoming, McpServer, Outgoing, SUPPORTED_PROTOCOL_VERSIONS};
+use anyhow::{Context, Result};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Path of the MCP endpoint.
+const ENDPOINT: &str = "/mcp";
+
+const SESSION_HEADER: &str = "mcp-session-id";
+const PROTOCOL_HEADER: &str = "mcp-protocol-version";
+
+/// Largest request body accepted.
+const MAX_BODY_BYTES: usize = 4 * 1024 * 1024;
+
+/// Most header lines read before a request is rejected.
+const MAX_HEADERS: usize = 100;
+
+/// Longest request line or header line accepted, in bytes.
+const MAX_LINE_BYTES: usize = 8 * 1024;
+
+/// Most connections served at once, each on its own thread. Connections
+/// beyond it are turned away with `503`.
+const MAX_CONNECTIONS: usize = 64;
+
+/// Most time spent answering a connection over `MAX_CONNECTIONS`.
+const TURN_AWAY_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// How long a connection may take to send its request.
+const REQUEST_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a session may go without a request before it is ended.
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// How often idle sessions are looked for.
+const SESSION_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// One client's state, shared by all connections that carry its session id.
+#[derive(Clone)]
+struct Session {
+    server: McpServer,
+    /// Stream opened with `GET`, used for messages not tied to a request.
+    events: EventStream,
+    /// Stops the session's subscription watcher.
+    stop_watcher: Arc<AtomicBool>,
+    /// When the session last received a request.
+    last_used: Arc<Mutex<Instant>>,
+}
+
+impl Session {
+    fn touch(&self) {
+        if let Ok(mut last_used) = self.last_used.lock() {
+            *last_used = Instant::now();
+        }
+    }
+
+    fn idle_for(&self) -> Duration {
+        self.last_used
+            .lock()
+            .map(|last_used| last_used.elapsed())
+            .unwrap_or_default()
+    }
+
+    /// Stop the session's background work and close its event stream.
+    fn end(&self) {
+        self.stop_watcher.store(true, Ordering::SeqCst);
+        self.events.detach();
+    }
+}
+
+#[derive(Clone)]
+struct HttpServer {
+    workspaces: Vec<PathBuf>,
+    /// Whether sessions offer only read-only tools.
+    read_only: bool,
+    sessions: Arc<Mutex<HashMap<String, Session>>>,
+    /// Installed plugins, listed for all sessions at once.
+    plugins: SharedPlugins,
+    /// Connections being served.
+    connections: Arc<AtomicUsize>,
+}
+
+/// A parsed HTTP request. Header names are lowercased.
+#[derive(Debug)]
+struct HttpRequest {
+    method: String,
+    path: String,
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+impl HttpRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+
+    fn accepts_event_stream(&self) -> bool {
+        self.header("accept")
+            .is_some_and(|accept| accept.contains("text/event-stream"))
+    }
+}
+
+/// Serve MCP over HTTP on `addr` until the process is stopped.
+pub(crate) fn serve(
+    addr: &str,
+    workspaces: Vec<PathBuf>,
+    read_only: bool,
+    allow_remote: bool,
+) -> Result<()> {
+    if !allow_remote {
+        ensure_loopback(addr)?;
+    }
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("Failed to listen on {addr}"))?;
+    eprintln!(
+        "meta-mcp listening on http://{}{ENDPOINT}",
+        listener.local_addr()?
+    );
+    HttpServer::new(workspaces, read_only).serve_on(listener);
+    Ok(())
+}
+
+impl HttpServer {
+    fn new(workspaces: Vec<PathBuf>, read_only: bool) -> Self {
+        Self {
+            workspaces,
+            read_only,
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            plugins: SharedPlugins::default(),
+            connections: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn serve_on(self, listener: TcpListener) {
+        let sweeper = self.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(SESSION_SWEEP_INTERVAL);
+            sweeper.expire_idle_sessions(SESSION_IDLE_TIMEOUT);
+        });
+
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("Failed to accept connection: {e}");
+                    continue;
+                }
+            };
+            if self.connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                self.connections.fetch_sub(1, Ordering::SeqCst);
+                turn_away(stream);
+                continue;
+            }
+            let server = self.clone();
+            std::thread::spawn(move || {
+                let result = server.handle_connection(stream);
+                server.connections.fetch_sub(1, Ordering::SeqCst);
+                if let Err(e) = result {
+                    eprintln!("HTTP connection failed: {e}");
+                }
+            });
+        }
+    }
+
+    /// Answer one request. Every response closes the connection.
+    fn handle_connection(&self, mut stream: TcpStream) -> Result<()> {
+        // Nothing is read after the request, so this only ends clients that
+        // connect and stall
+        stream.set_read_timeout(Some(REQUEST_READ_TIMEOUT))?;
+        let request = match read_request(&mut BufReader::new(stream.try_clone()?)) {
+            Ok(request) => request,
+            Err(e) => return respond(&mut stream, 400, &[], &e.to_string()),
+        };
+
+        // Browsers send an Origin; only local pages may talk to the server, so
+        // a remote site cannot reach it through DNS rebinding
+        if request
+            .header("origin")
+            .is_some_and(|o| !is_local_origin(o))
+        {
+            return respond(&mut stream, 403, &[], "Origin not allowed");
+        }
+        if request.path != ENDPOINT {
+            return respond(&mut stream, 404, &[], "Not found");
+        }
+        if let Some(version) = request.header(PROTOCOL_HEADER) {
+            if !SUPPORTED_PROTOCOL_VERSIONS.contains(&version) {
+                let message = format!("Unsupported protocol version: {version}");
+                return respond(&mut stream, 400, &[], &message);
+            }
+        }
+
+        match request.method.as_str() {
+            "POST" => self.handle_post(stream, &request),
+            "GET" => self.handle_get(stream, &request),
+            "DELETE" => self.handle_delete(stream, &request),
+            _ => respond(
+                &mut stream,
+                405,
+                &[("Allow", "GET, POST, DELETE".to_string())],
+                "Method not allowed",
+            ),
+        }
+    }
+
+    fn handle_post(&self, mut stream: TcpStream, request: &HttpRequest) -> Result<()> {
+        let body = String::from_utf8_lossy(&request.body);
+
+        let (session_id, session) = match request.header(SESSION_HEADER) {
+            Some(id) => match self.session(id) {
+                Some(session) => (id.to_string(), session),
+                None => return respond(&mut stream, 404, &[], "Unknown session"),
+            },
+            None if is_initialize(&body) => self.create_session()?,
+            None => return respond(&mut stream, 400, &[], "Missing Mcp-Session-Id header"),
+        };
+        let headers = [("Mcp-Session-Id", session_id)];
+
+        let message = match session.server.parse_message(&body) {
+            Ok(Incoming::Request(message)) => message,
+            Ok(Incoming::Response(response)) => {
+                session.server.handle_client_response(response);
+                return respond(&mut stream, 202, &headers, "");
+            }
+            Err(response) => {
+                return respond_json(
+                    &mut stream,
+                    400,
+                    &headers,
+                    &serde_json::to_string(&*response)?,
+                )
+            }
+        };
+
+        if message.is_notification() {
+            session.server.handle_notification(&message);
+            return respond(&mut stream, 202, &headers, "");
+        }
+
+        let server = session.server.begin_request(&message)?;
+        if request.accepts_event_stream() {
+            start_event_stream(&mut stream, &headers)?;
+            let server = McpServer {
+                outgoing: Outgoing::new(Box::new(EventStream::to(stream))),
+                ..server
+            };
+            server.finish_request(&message);
+            return Ok(());
+        }
+
+        // Plain JSON can only carry the response, which is sent last
+        let captured = Captured::default();
+        let server = McpServer {
+            outgoing: Outgoing::new(Box::new(captured.clone())),
+            ..server
+        };
+        server.finish_request(&message);
+        match captured.last_message() {
+            Some(response) => respond_json(&mut stream, 200, &headers, &response),
+            // Cancelled requests are not answered
+            None => respond(&mut stream, 202, &headers, ""),
+        }
+    }
+
+    fn handle_get(&self, mut stream: TcpStream, request: &HttpRequest) -> Result<()> {
+        let (session_id, session) = match self.require_session(&mut stream, request)? {
+            Some(found) => found,
+            None => return Ok(()),
+        };
+        if !request.accepts_event_stream() {
+            return respond(
+                &mut stream,
+                406,
+                &[],
+                "GET requires Accept: text/event-stream",
+            );
+        }
+
+        start_event_stream(&mut stream, &[("Mcp-Session-Id", session_id)])?;
+        session.events.attach(stream);
+        Ok(())
+    }
+
+    fn handle_delete(&self, mut stream: TcpStream, request: &HttpRequest) -> Result<()> {
+        let (session_id, session) = match self.require_session(&mut stream, request)? {
+            Some(found) => found,
+            None => return Ok(()),
+        };
+
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.remove(&session_id);
+        }
+        session.end();
+        respond(&mut stream, 200, &[], "")
+    }
+
+    /// End every session that has gone without a request for `idle`.
+    fn expire_idle_sessions(&self, idle: Duration) {
+        let expired: Vec<Session> = match self.sessions.lock() {
+            Ok(mut sessions) => {
+                let ids: Vec<String> = sessions
+                    .iter()
+                    .filter(|(_, session)| session.idle_for() >= idle)
+                    .map(|(id, _)| id.clone())
+                    .collect();
+                ids.iter().filter_map(|id| sessions.remove(id)).collect()
+            }
+            Err(_) => return,
+        };
+        for session in expired {
+            session.end();
+        }
+    }
+
+    /// Look up the request's session, answering 400/404 if there is none.
+    fn require_session(
+        &self,
+        stream: &mut TcpStream,
+        request: &HttpRequest,
+    ) -> Result<Option<(String, Session)>> {
+        let id = match request.header(SESSION_HEADER) {
+            Some(id) => id,
+            None => {
+                respond(stream, 400, &[], "Missing Mcp-Session-Id header")?;
+                return Ok(None);
+            }
+        };
+        match self.session(id) {
+            Some(session) => Ok(Some((id.to_string(), session))),
+            None => {
+                respond(stream, 404, &[], "Unknown session")?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn session(&self, id: &str) -> Option<Session> {
+        let session = self.sessions.lock().ok()?.get(id).cloned()?;
+        session.touch();
+        Some(session)
+    }
+
+    fn create_session(&self) -> Result<(String, Session)> {
+        let events = EventStream::default();
+        let server = McpServer {
+            read_only: self.read_only,
+            plugins: self.plugins.clone(),
+            ..McpServer::with_workspaces(
+                self.workspaces.clone(),
+                Outgoing::new(Box::new(events.clone())),
+            )
+        };
+        let session = Session {
+            stop_watcher: server.spawn_watcher(),
+            server,
+            events,
+            last_used: Arc::new(Mutex::new(Instant::now())),
+        };
+
+        let id = new_session_id();
+        self.sessions
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Sessions lock poisoned"))?
+            .insert(id.clone(), session.clone());
+        Ok((id, session))
+    }
+}
+
+/// Writer that sends each message written through `Outgoing` as a server-sent
+/// event. Messages are dropped while no stream is attached.
+#[derive(Clone, Default)]
+struct EventStream {
+    target: Arc<Mutex<Option<TcpStream>>>,
+    /// Bytes of the message being written; framed and sent on flush.
+    pending: Vec<u8>,
+}
+
+impl EventStream {
+    fn to(stream: TcpStream) -> Self {
+        let events = Self::default();
+        events.attach(stream);
+        events
+    }
+
+    /// Send future events to `stream`, closing any previous one.
+    fn attach(&self, stream: TcpStream) {
+        if let Ok(mut target) = self.target.lock() {
+            *target = Some(stream);
+        }
+    }
+
+    fn detach(&self) {
+        if let Ok(mut target) = self.target.lock() {
+            *target = None;
+        }
+    }
+}
+
+impl Write for EventStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        let pending = std::mem::take(&mut self.pending);
+        let mut target = self
+            .target
+            .lock()
+            .map_err(|_| std::io::Error::other("Event stream lock poisoned"))?;
+
+        if let Some(stream) = target.as_mut() {
+            let mut event = String::new();
+            for line in String::from_utf8_lossy(&pending).lines() {
+                event.push_str("data: ");
+                event.push_str(line);
+                event.push('\n');
+            }
+            event.push('\n');
+
+            // The client went away; stop writing to it
+            if stream
+                .write_all(event.as_bytes())
+                .and_then(|_| stream.flush())
+                .is_err()
+            {
+                *target = None;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writer that keeps what a request sends so it can be returned as JSON.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Captured {
+    fn last_message(&self) -> Option<String> {
+        let bytes = self.0.lock().ok()?;
+        String::from_utf8_lossy(&bytes)
+            .lines()
+            .last()
+            .map(str::to_string)
+    }
+}
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .lock()
+            .map_err(|_| std::io::Error::other("Capture lock poisoned"))?
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Read one line of the request head, refusing lines over `MAX_LINE_BYTES` so
+/// a client cannot make the server buffer without limit.
+fn read_line(reader: &mut impl BufRead) -> Result<String> {
+    let mut line = String::new();
+    reader
+        .by_ref()
+        .take(MAX_LINE_BYTES as u64 + 1)
+        .read_line(&mut line)?;
+    if line.len() > MAX_LINE_BYTES {
+        anyhow::bail!("Request line or header exceeds {MAX_LINE_BYTES} bytes");
+    }
+    Ok(line)
+}
+
+fn read_request(reader: &mut impl BufRead) -> Result<HttpRequest> {
+    let line = read_line(reader)?;
+    let mut parts = line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target),
+        _ => anyhow::bail!("Malformed request line"),
+    };
+    let path = target.split('?').next().unwrap_or(target).to_string();
+
+    let mut headers = HashMap::new();
+    for _ in 0..MAX_HEADERS {
+        let header = read_line(reader)?;
+        let header = header.trim_end_matches(['\r', '\n']);
+        if header.is_empty() {
+            break;
+        }
+        let (name, value) = header
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("Malformed header: {header}"))?;
+        headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+    }
+
+    if headers
+        .get("transfer-encoding")
+        .is_some_and(|e| e.contains("chunked"))
+    {
+        anyhow::bail!("Chunked request bodies are not supported; send Content-Length");
+    }
+    let length: usize = match headers.get("content-length") {
+        Some(length) => length.parse().context("Invalid Content-Length")?,
+        None => 0,
+    };
+    if length > MAX_BODY_BYTES {
+        anyhow::bail!("Request body exceeds {MAX_BODY_BYTES} bytes");
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    Ok(HttpRequest {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+/// Answer `503` without a thread of its own, spending at most
+/// `TURN_AWAY_TIMEOUT` on the connection. What the client sent is read and
+/// dropped first, since closing a socket with unread data resets it and the
+/// client would never see the answer.
+fn turn_away(mut stream: TcpStream) {
+    let started = Instant::now();
+    let _ = stream.set_read_timeout(Some(TURN_AWAY_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(TURN_AWAY_TIMEOUT));
+    let _ = respond(&mut stream, 503, &[], "Too many connections");
+    let _ = stream.shutdown(std::net::Shutdown::Write);
+    let mut discard = [0; 8192];
+    while started.elapsed() < TURN_AWAY_TIMEOUT {
+        match stream.read(&mut discard) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+    }
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    status: u16,
+    headers: &[(&str, String)],
+    body: &str,
+) -> Result<()> {
+    write_response(stream, status, "text/plain; charset=utf-8", headers, body)
+}
+
+fn respond_json(
+    stream: &mut TcpStream,
+    status: u16,
+    headers: &[(&str, String)],
+    body: &str,
+) -> Result<()> {
+    write_response(stream, status, "application/json", headers, body)
+}
+
+fn write_response(
+    stream: &mut TcpStream,
+    status: u16,
+    content_type: &str,
+    headers: &[(&str, String)],
+    body: &str,
+) -> Result<()> {
+    let mut head = format!("HTTP/1.1 {status} {}\r\n", reason(status));
+    for (name, value) in headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    if !body.is_empty() {
+        head.push_str(&format!("Content-Type: {content_type}\r\n"));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    ));
+
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body.as_bytes())?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Send the head of a `text/event-stream` response. The body runs until the
+/// connection is closed.
+fn start_event_stream(stream: &mut TcpStream, headers: &[(&str, String)]) -> Result<()> {
+    let mut head = "HTTP/1.1 200 OK\r\n".to_string();
+    for (name, value) in headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str(
+        "Content-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+    );
+    stream.write_all(head.as_bytes())?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        406 => "Not Acceptable",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+fn is_initialize(body: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|message| message.get("method")?.as_str().map(|m| m == "initialize"))
+        .unwrap_or(false)
+}
+
+/// Fail unless every address `addr` resolves to is a loopback address.
+fn ensure_loopback(addr: &str) -> Result<()> {
+    let resolved: Vec<_> = addr
+        .to_socket_addrs()
+        .with_context(|| format!("Invalid address: {addr}"))?
+        .collect();
+    if resolved.is_empty() || resolved.iter().any(|a| !a.ip().is_loopback()) {
+        anyhow::bail!(
+            "Refusing to serve {addr}: it is reachable from other machines and HTTP \
+             clients are not authenticated. Pass --allow-remote to serve it anyway"
+        );
+    }
+    Ok(())
+}
+
+/// Whether an `Origin` header names a page served from this machine.
+fn is_local_origin(origin: &str) -> bool {
+    let authority = origin.split("://").nth(1).unwrap_or(origin);
+    let authority = authority.split('/').next().unwrap_or("");
+    let host = match authority.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next(),
+        None => authority.split(':').next(),
+    };
+    matches!(host, Some("localhost") | Some("127.0.0.1") | Some("::1"))
+}
+
+/// A hard-to-guess session id, from the OS-seeded keys of `RandomState`.
+fn new_session_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let state = RandomState::new();
+    (0..2)
+        .map(|_| {
+            let mut hasher = state.build_hasher();
+            hasher.write_u64(COUNTER.fetch_add(1, Ordering::SeqCst));
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+
+    fn start_server() -> (SocketAddr, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join(".meta"), r#"{"projects": {}}"#).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = HttpServer::new(vec![tmp.path().to_path_buf()], false);
+        std::thread::spawn(move || server.serve_on(listener));
+        (addr, tmp)
+    }
+
+    /// Send a raw request and return the status, headers and body.
+    fn send(
+        addr: SocketAddr,
+        method: &str,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) -> (u16, HashMap<String, String>, String) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        let mut request = format!("{method} /mcp HTTP/1.1\r\nHost: {addr}\r\n");
+        for (name, value) in headers {
+            request.push_str(&format!("{name}: {value}\r\n"));
+        }
+        request.push_str(&format!("Content-Length: {}\r\n\r\n{body}", body.len()));
+        stream.write_all(request.as_bytes()).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let mut lines = head.lines();
+        let status = lines
+            .next()
+            .unwrap()
+            .split(' ')
+            .nth(1)
+            .unwrap()
+            .parse()
+            .unwrap();
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.to_lowercase(), value.trim().to_string()))
+            .collect();
+        (status, headers, body.to_string())
+    }
+
+    const INITIALIZE: &str = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-06-18"}}"#;
+
+    #[test]
+    fn test_initialize_starts_session_over_sse() {
+        let (addr, _tmp) = start_server();
+        let (status, headers, body) = send(
+            addr,
+            "POST",
+            &[("Accept", "application/json, text/event-stream")],
+            INITIALIZE,
+        );
+
+        assert_eq!(status, 200);
+        assert_eq!(headers["content-type"], "text/event-stream");
+        assert_eq!(headers["mcp-session-id"].len(), 32);
+        let data = body.trim().strip_prefix("data: ").unwrap();
+        let message: serde_json::Value = serde_json::from_str(data).unwrap();
+        assert_eq!(message["result"]["protocolVersion"], "2025-06-18");
+    }
+
+    #[test]
+    fn test_session_lifecycle() {
+        let (addr, _tmp) = start_server();
+        let (_, headers, _) = send(addr, "POST", &[], INITIALIZE);
+        let session = headers["mcp-session-id"].as_str();
+
+        // Requests need the session
+        let list = r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#;
+        assert_eq!(send(addr, "POST", &[], list).0, 400);
+
+        let (status, headers, body) = send(addr, "POST", &[("Mcp-Session-Id", session)], list);
+        assert_eq!(status, 200);
+        assert_eq!(headers["content-type"], "application/json");
+        let message: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(message["id"], 2);
+        assert!(message["result"]["tools"].is_array());
+
+        let initialized = r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#;
+        let (status, _, body) = send(addr, "POST", &[("Mcp-Session-Id", session)], initialized);
+        assert_eq!(status, 202);
+        assert!(body.is_empty());
+
+        assert_eq!(
+            send(addr, "DELETE", &[("Mcp-Session-Id", session)], "").0,
+            200
+        );
+        assert_eq!(
+            send(addr, "POST", &[("Mcp-Session-Id", session)], list).0,
+            404
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_requests() {
+        let (addr, _tmp) = start_server();
+
+        assert_eq!(
+            send(
+                addr,
+                "POST",
+                &[("Origin", "https://evil.example")],
+                INITIALIZE
+            )
+            .0,
+            403
+        );
+        assert_eq!(
+            send(
+                addr,
+                "POST",
+                &[("MCP-Protocol-Version", "1999-01-01")],
+                INITIALIZE
+            )
+            .0,
+            400
+        );
+        assert_eq!(send(addr, "PUT", &[], INITIALIZE).0, 405);
+
+        let (_, headers, _) = send(addr, "POST", &[], INITIALIZE);
+        let session = headers["mcp-session-id"].as_str();
+        let (status, _, body) = send(addr, "POST", &[("Mcp-Session-Id", session)], "{oops");
+        assert_eq!(status, 400);
+        assert!(body.contains("-32700"));
+    }
+
+    #[test]
+    fn test_rejects_oversized_head() {
+        let long = "x".repeat(MAX_LINE_BYTES + 1);
+        let request = format!("GET /mcp HTTP/1.1\r\nX-Long: {long}\r\n\r\n");
+        assert!(read_request(&mut request.as_bytes()).is_err());
+
+        let request = format!("GET /{long} HTTP/1.1\r\n\r\n");
+        assert!(read_request(&mut request.as_bytes()).is_err());
+
+        let request = "GET /mcp HTTP/1.1\r\nAccept: text/event-stream\r\n\r\n";
+        let parsed = read_request(&mut request.as_bytes()).unwrap();
+        assert_eq!(parsed.header("accept"), Some("text/event-stream"));
+    }
+
+    #[test]
+    fn test_turns_away_connections_over_the_limit() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join(".meta"), r#"{"projects": {}}"#).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = HttpServer::new(vec![tmp.path().to_path_buf()], false);
+        server.connections.store(MAX_CONNECTIONS, Ordering::SeqCst);
+        let connections = server.connections.clone();
+        std::thread::spawn(move || server.serve_on(listener));
+
+        assert_eq!(send(addr, "POST", &[], INITIALIZE).0, 503);
+
+        connections.store(0, Ordering::SeqCst);
+        assert_eq!(send(addr, "POST", &[], INITIALIZE).0, 200);
+    }
+
+    #[test]
+    fn test_idle_sessions_expire() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join(".meta"), r#"{"projects": {}}"#).unwrap();
+        let server = HttpServer::new(vec![tmp.path().to_path_buf()], false);
+        let (id, session) = server.create_session().unwrap();
+
+        server.expire_idle_sessions(SESSION_IDLE_TIMEOUT);
+        assert!(server.session(&id).is_some());
+
+        server.expire_idle_sessions(Duration::ZERO);
+        assert!(server.session(&id).is_none());
+        assert!(session.stop_watcher.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_only_loopback_by_default() {
+        assert!(ensure_loopback("127.0.0.1:8080").is_ok());
+        assert!(ensure_loopback("[::1]:8080").is_ok());
+        assert!(ensure_loopback("localhost:8080").is_ok());
+        assert!(ensure_loopback("0.0.0.0:8080").is_err());
+        assert!(ensure_loopback("192.0.2.1:8080").is_err());
+        assert!(ensure_loopback("nonsense").is_err());
+    }
+
+    #[test]
+    fn test_is_local_origin() {
+        assert!(is_local_origin("http://localhost:3000"));
+        assert!(is_local_origin("http://127.0.0.1"));
+        assert!(is_local_origin("http://[::1]:8080"));
+        assert!(!is_local_origin("http://localhost.evil.example"));
+        assert!(!is_local_origin("null"));
+    }
+}
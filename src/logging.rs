@@ -0,0 +1,136 @@
+//! MCP logging: `logging/setLevel` and `notifications/message`.
+//!
+//! Internal events (spawned commands and their durations, projects skipped
+//! because their directory is missing) are sent to the client as log messages,
+//! so an unexpectedly empty tool result can be explained from inside the client.
+
+use super::{McpServer, RpcError, SERVER_NAME};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Syslog severities, in increasing order, as used by MCP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LogLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+/// Level used until the client calls `logging/setLevel`.
+pub(crate) const DEFAULT_LOG_LEVEL: LogLevel = LogLevel::Info;
+
+impl McpServer {
+    pub(crate) fn handle_set_level(&self, params: &serde_json::Value) -> Result<serde_json::Value> {
+        let level = params
+            .get("level")
+            .cloned()
+            .and_then(|level| serde_json::from_value::<LogLevel>(level).ok())
+            .ok_or_else(|| RpcError::new(-32602, "Missing or invalid 'level' parameter"))?;
+
+        *self
+            .log_level
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Log level lock poisoned"))? = level;
+        Ok(serde_json::json!({}))
+    }
+
+    /// Send a log message to the client if `level` is at or above the level it
+    /// asked for.
+    pub(crate) fn log(&self, level: LogLevel, message: impl Into<String>) {
+        let enabled = self
+            .log_level
+            .lock()
+            .map(|min| level >= *min)
+            .unwrap_or(false);
+        if !enabled {
+            return;
+        }
+
+        let message = message.into();
+        let params = serde_json::json!({
+            "level": level,
+            "logger": SERVER_NAME,
+            "data": message,
+        });
+        if let Err(e) = self.send_notification("notifications/message", params) {
+            eprintln!("Failed to send log message ({e}): {message}");
+        }
+    }
+
+    /// Log why a project was left out of a tool's results.
+    pub(crate) fn log_skipped(&self, project: &str, reason: &str) {
+        self.log(
+            LogLevel::Info,
+            format!("Skipped project '{project}': {reason}"),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::SharedBuffer;
+    use crate::Outgoing;
+
+    #[test]
+    fn test_set_level_filters_messages() {
+        let buffer = SharedBuffer::default();
+        let server = McpServer::with_meta_dir(None, Outgoing::new(Box::new(buffer.clone())));
+
+        server.log(LogLevel::Debug, "hidden by default");
+        server.log(LogLevel::Info, "shown by default");
+        server
+            .handle_set_level(&serde_json::json!({"level": "error"}))
+            .unwrap();
+        server.log(LogLevel::Warning, "hidden at error");
+        server.log(LogLevel::Critical, "shown at error");
+
+        let messages = buffer.messages();
+        let data: Vec<&str> = messages
+            .iter()
+            .map(|m| m["params"]["data"].as_str().unwrap())
+            .collect();
+        assert_eq!(data, ["shown by default", "shown at error"]);
+        assert_eq!(messages[1]["method"], "notifications/message");
+        assert_eq!(messages[1]["params"]["level"], "critical");
+        assert_eq!(messages[1]["params"]["logger"], SERVER_NAME);
+    }
+
+    #[test]
+    fn test_missing_project_is_logged() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join(".meta"), r#"{"projects": {"api": "x"}}"#).unwrap();
+        let buffer = SharedBuffer::default();
+        let server = McpServer::with_meta_dir(
+            Some(tmp.path().to_path_buf()),
+            Outgoing::new(Box::new(buffer.clone())),
+        );
+
+        let output = server
+            .tool_search_code(&serde_json::json!({"pattern": "fn"}))
+            .unwrap();
+        assert_eq!(output.into_value(), serde_json::json!([]));
+
+        let messages = buffer.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            messages[0]["params"]["data"],
+            "Skipped project 'api': directory does not exist"
+        );
+    }
+
+    #[test]
+    fn test_set_level_rejects_unknown_level() {
+        let server = McpServer::with_meta_dir(None, Outgoing::new(Box::new(std::io::sink())));
+        let err = server
+            .handle_set_level(&serde_json::json!({"level": "verbose"}))
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<RpcError>().unwrap().code, -32602);
+    }
+}
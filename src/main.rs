@@ -9,12 +9,56 @@ use meta_cli::git_utils;
 use meta_cli::query::{Query, RepoState, WorkspaceState};
 use meta_core::config::{self, ProjectInfo};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, VecDeque};
 use std::io::{BufRead, BufReader, Write};
 use std::path::PathBuf;
 use std::process::Command;
-
-/// MCP Protocol version
-const PROTOCOL_VERSION: &str = "2024-11-05";
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use executor::selected_projects;
+use logging::{LogLevel, DEFAULT_LOG_LEVEL};
+use plugins::SharedPlugins;
+use process::failed_project;
+use roots::PendingClientRequests;
+use server_config::CachedConfig;
+use workspaces::Workspaces;
+
+mod completions;
+mod executor;
+mod http;
+mod logging;
+mod plugins;
+mod process;
+mod progress;
+mod prompts;
+mod resources;
+mod roots;
+mod server_config;
+#[cfg(unix)]
+mod socket;
+mod subscriptions;
+mod tools;
+mod validation;
+mod workspace_tools;
+mod workspaces;
+
+/// MCP protocol revisions this server speaks, newest first.
+const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// Revision assumed until (and unless) the client asks for a newer one.
+const DEFAULT_PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// First revision that defines tool annotations.
+const TOOL_ANNOTATIONS_VERSION: &str = "2025-03-26";
+
+/// First revision that defines `outputSchema` and `structuredContent`.
+const STRUCTURED_CONTENT_VERSION: &str = "2025-06-18";
+
+/// First revision that defines the `completions` capability.
+const COMPLETIONS_VERSION: &str = "2025-03-26";
 
 /// Server information
 const SERVER_NAME: &str = "meta-mcp";
@@ -33,6 +77,32 @@ struct JsonRpcRequest {
     params: serde_json::Value,
 }
 
+/// The client's answer to a request the server sent it.
+#[derive(Debug, Deserialize)]
+struct ClientResponse {
+    id: serde_json::Value,
+    result: Option<serde_json::Value>,
+    error: Option<serde_json::Value>,
+}
+
+/// A message received from the client.
+#[derive(Debug)]
+enum Incoming {
+    /// A request or notification.
+    Request(JsonRpcRequest),
+    /// A response to a request the server sent.
+    Response(ClientResponse),
+}
+
+impl JsonRpcRequest {
+    /// Messages without an id are notifications: act on them, never reply.
+    /// Anything with an id is a request and gets a response, whatever its
+    /// method is called.
+    fn is_notification(&self) -> bool {
+        self.id.is_none()
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct JsonRpcResponse {
     jsonrpc: String,
@@ -43,6 +113,13 @@ struct JsonRpcResponse {
     error: Option<JsonRpcError>,
 }
 
+#[derive(Debug, Serialize)]
+struct JsonRpcNotification {
+    jsonrpc: String,
+    method: String,
+    params: serde_json::Value,
+}
+
 #[derive(Debug, Serialize)]
 struct JsonRpcError {
     code: i32,
@@ -69,6 +146,11 @@ struct InitializeResult {
 #[derive(Debug, Serialize)]
 struct ServerCapabilities {
     tools: ToolsCapability,
+    resources: ResourcesCapability,
+    prompts: PromptsCapability,
+    logging: LoggingCapability,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    completions: Option<CompletionsCapability>,
 }
 
 #[derive(Debug, Serialize)]
@@ -78,11 +160,86 @@ struct ToolsCapability {
 }
 
 #[derive(Debug, Serialize)]
+struct PromptsCapability {
+    #[serde(rename = "listChanged")]
+    list_changed: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct LoggingCapability {}
+
+#[derive(Debug, Serialize)]
+struct CompletionsCapability {}
+
+#[derive(Debug, Serialize)]
+struct ResourcesCapability {
+    subscribe: bool,
+    #[serde(rename = "listChanged")]
+    list_changed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
 struct Tool {
     name: String,
     description: String,
     #[serde(rename = "inputSchema")]
     input_schema: serde_json::Value,
+    #[serde(rename = "outputSchema", skip_serializing_if = "Option::is_none")]
+    output_schema: Option<serde_json::Value>,
+    annotations: ToolAnnotations,
+}
+
+/// Behaviour hints clients use to decide which calls need confirmation.
+#[derive(Debug, Clone, Serialize)]
+struct ToolAnnotations {
+    #[serde(rename = "readOnlyHint")]
+    read_only_hint: bool,
+    #[serde(rename = "destructiveHint")]
+    destructive_hint: bool,
+    #[serde(rename = "idempotentHint")]
+    idempotent_hint: bool,
+    #[serde(rename = "openWorldHint")]
+    open_world_hint: bool,
+}
+
+impl ToolAnnotations {
+    /// Only inspects the workspace.
+    fn read_only() -> Self {
+        Self {
+            read_only_hint: true,
+            destructive_hint: false,
+            idempotent_hint: true,
+            open_world_hint: false,
+        }
+    }
+
+    /// Modifies the workspace without discarding existing work.
+    fn additive(idempotent: bool) -> Self {
+        Self {
+            read_only_hint: false,
+            destructive_hint: false,
+            idempotent_hint: idempotent,
+            open_world_hint: false,
+        }
+    }
+
+    /// May discard uncommitted work, build artifacts or history.
+    fn destructive(idempotent: bool) -> Self {
+        Self {
+            read_only_hint: false,
+            destructive_hint: true,
+            idempotent_hint: idempotent,
+            open_world_hint: false,
+        }
+    }
+
+    /// Also talks to remotes or runs arbitrary external commands.
+    fn open_world(self) -> Self {
+        Self {
+            open_world_hint: true,
+            ..self
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -93,6 +250,8 @@ struct ListToolsResult {
 #[derive(Debug, Serialize)]
 struct CallToolResult {
     content: Vec<ToolContent>,
+    #[serde(rename = "structuredContent", skip_serializing_if = "Option::is_none")]
+    structured_content: Option<serde_json::Value>,
     #[serde(rename = "isError", skip_serializing_if = "Option::is_none")]
     is_error: Option<bool>,
 }
@@ -104,69 +263,424 @@ struct ToolContent {
     text: String,
 }
 
+/// An error that maps to a specific JSON-RPC error code.
+///
+/// Handlers return these through `anyhow` when the generic `-32603 Internal
+/// error` code would be misleading; `handle_request` downcasts to recover the code.
+#[derive(Debug)]
+struct RpcError {
+    code: i32,
+    message: String,
+    data: Option<serde_json::Value>,
+}
+
+impl RpcError {
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    fn with_data(self, data: serde_json::Value) -> Self {
+        Self {
+            data: Some(data),
+            ..self
+        }
+    }
+}
+
+impl std::fmt::Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for RpcError {}
+
 // ============================================================================
 // Meta-specific Types
 // ============================================================================
 
+/// What a tool produced.
+///
+/// `Json` output is rendered as pretty-printed text for every client and is also
+/// returned as `structuredContent` to clients that negotiated support for it.
+#[derive(Debug)]
+enum ToolOutput {
+    Text(String),
+    Json(serde_json::Value),
+}
+
+impl ToolOutput {
+    fn json<T: Serialize>(value: &T) -> Result<Self> {
+        Ok(Self::Json(serde_json::to_value(value)?))
+    }
+
+    /// The value for `structuredContent`, which must be a JSON object.
+    /// Array results are wrapped as `{"results": [...]}`.
+    fn structured(&self) -> Option<serde_json::Value> {
+        match self {
+            Self::Text(_) => None,
+            Self::Json(value @ serde_json::Value::Object(_)) => Some(value.clone()),
+            Self::Json(value) => Some(serde_json::json!({ "results": value })),
+        }
+    }
+
+    fn into_value(self) -> serde_json::Value {
+        match self {
+            Self::Text(text) => serde_json::Value::String(text),
+            Self::Json(value) => value,
+        }
+    }
+}
+
+impl std::fmt::Display for ToolOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text(text) => f.write_str(text),
+            Self::Json(value) => {
+                let text = serde_json::to_string_pretty(value).map_err(|_| std::fmt::Error)?;
+                f.write_str(&text)
+            }
+        }
+    }
+}
+
+/// Output schema for tools whose JSON output is an array of per-project objects.
+fn results_schema(item: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "results": {
+                "type": "array",
+                "items": item
+            }
+        },
+        "required": ["results"]
+    })
+}
+
 // ============================================================================
 // MCP Server
 // ============================================================================
 
+/// Newline-delimited JSON sink shared by responses and server notifications.
+///
+/// Cloning shares the underlying writer, so messages from background threads
+/// never interleave with responses.
+#[derive(Clone)]
+struct Outgoing(Arc<Mutex<Box<dyn Write + Send>>>);
+
+impl Outgoing {
+    fn new(writer: Box<dyn Write + Send>) -> Self {
+        Self(Arc::new(Mutex::new(writer)))
+    }
+
+    fn send<T: Serialize>(&self, message: &T) -> Result<()> {
+        let json = serde_json::to_string(message)?;
+        let mut writer = self
+            .0
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Output writer poisoned"))?;
+        writeln!(writer, "{json}")?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// State shared between an in-flight request and `notifications/cancelled`.
+#[derive(Debug, Default)]
+struct RequestContext {
+    cancelled: AtomicBool,
+    /// `_meta.progressToken` from the request, if the client wants progress.
+    /// Taken by the first progress reporter so nested tool calls stay quiet.
+    progress_token: Mutex<Option<serde_json::Value>>,
+}
+
+impl RequestContext {
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Handle to the server state. Clones share the same subscriptions and output.
+#[derive(Clone)]
 struct McpServer {
+    /// Workspace this handle works on, fixed for the lifetime of a request.
     meta_dir: Option<PathBuf>,
+    /// Workspaces tool calls can pick from. The default one, which new
+    /// requests start with, changes when the client's roots do.
+    workspaces: Arc<Mutex<Workspaces>>,
+    outgoing: Outgoing,
+    /// Resource URIs the client has subscribed to via `resources/subscribe`.
+    subscriptions: Arc<Mutex<BTreeSet<String>>>,
+    /// Protocol revision agreed on during `initialize`.
+    protocol_version: Arc<Mutex<&'static str>>,
+    /// Minimum level of `notifications/message` the client wants to see.
+    log_level: Arc<Mutex<LogLevel>>,
+    /// Requests currently being handled, keyed by their serialized id.
+    in_flight: Arc<Mutex<HashMap<String, Arc<RequestContext>>>>,
+    /// The request this handle is working on, if any. Set on the per-request
+    /// clone so spawned processes can be killed when the request is cancelled.
+    request: Option<Arc<RequestContext>>,
+    /// How long each process spawned for the current tool call may run. Set on
+    /// the per-call clone; `None` outside tool calls.
+    timeout: Option<Duration>,
+    /// Most bytes kept of each output stream of a process spawned for the
+    /// current tool call. Set on the per-call clone like `timeout`.
+    output_limit: Option<usize>,
+    /// Requests sent to the client that are waiting for its response.
+    client_requests: PendingClientRequests,
+    /// Whether the client declared the `roots` capability.
+    client_roots: Arc<AtomicBool>,
+    /// Installed meta plugins, once listed.
+    plugins: SharedPlugins,
+    /// Server settings of each workspace, as last read.
+    server_configs: Arc<Mutex<HashMap<PathBuf, CachedConfig>>>,
+    /// Offer only read-only tools, whatever the workspace settings say.
+    read_only: bool,
 }
 
 impl McpServer {
+    #[cfg(test)]
     fn new() -> Self {
-        // Find .meta config in current directory or parents
-        let meta_dir = std::env::current_dir()
-            .ok()
-            .and_then(|dir| config::find_meta_config(&dir, None))
-            .map(|(config_path, _)| config_path.parent().unwrap().to_path_buf());
+        Self::with_meta_dir(find_meta_dir(), Outgoing::new(Box::new(std::io::stdout())))
+    }
+
+    #[cfg(test)]
+    fn with_meta_dir(meta_dir: Option<PathBuf>, outgoing: Outgoing) -> Self {
+        Self::with_workspaces(meta_dir.into_iter().collect(), outgoing)
+    }
 
-        Self { meta_dir }
+    fn with_workspaces(workspaces: Vec<PathBuf>, outgoing: Outgoing) -> Self {
+        let workspaces = Workspaces::new(workspaces);
+        Self {
+            meta_dir: workspaces.default_dir(),
+            workspaces: Arc::new(Mutex::new(workspaces)),
+            outgoing,
+            subscriptions: Arc::new(Mutex::new(BTreeSet::new())),
+            protocol_version: Arc::new(Mutex::new(DEFAULT_PROTOCOL_VERSION)),
+            log_level: Arc::new(Mutex::new(DEFAULT_LOG_LEVEL)),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            request: None,
+            timeout: None,
+            output_limit: None,
+            client_requests: Arc::new(Mutex::new(HashMap::new())),
+            client_roots: Arc::new(AtomicBool::new(false)),
+            plugins: SharedPlugins::default(),
+            server_configs: Arc::new(Mutex::new(HashMap::new())),
+            read_only: false,
+        }
     }
 
-    fn run(&mut self) -> Result<()> {
+    fn run(&self) -> Result<()> {
         let stdin = std::io::stdin();
-        let mut stdout = std::io::stdout();
-        let reader = BufReader::new(stdin.lock());
+        self.serve(BufReader::new(stdin.lock()))
+    }
+
+    /// Handle newline-delimited messages from `input` until it is closed,
+    /// answering through `self.outgoing`. At most `request_workers()` requests
+    /// are handled at once; the rest wait their turn.
+    fn serve(&self, input: impl BufRead) -> Result<()> {
+        let stop_watcher = self.spawn_watcher();
 
-        for line in reader.lines() {
+        let queue = Arc::new(Mutex::new(RequestQueue::default()));
+        let max_workers = request_workers();
+        let mut workers = Vec::new();
+        for line in input.lines() {
             let line = line?;
-            if line.is_empty() {
+            if line.trim().is_empty() {
                 continue;
             }
 
-            let request: JsonRpcRequest = match serde_json::from_str(&line) {
-                Ok(req) => req,
-                Err(e) => {
-                    eprintln!("Failed to parse request: {e}");
-                    continue;
-                }
-            };
+            workers.retain(|worker: &JoinHandle<()>| !worker.is_finished());
+            if let Some(request) = self.dispatch(&line)? {
+                workers.extend(RequestQueue::push(&queue, request, max_workers)?);
+            }
+        }
 
-            let response = self.handle_request(&request);
-            let response_json = serde_json::to_string(&response)?;
-            writeln!(stdout, "{response_json}")?;
-            stdout.flush()?;
+        // The client may close stdin right after its last request; answer it
+        // before exiting
+        for worker in workers {
+            let _ = worker.join();
         }
+        stop_watcher.store(true, Ordering::SeqCst);
 
         Ok(())
     }
 
-    fn handle_request(&self, request: &JsonRpcRequest) -> JsonRpcResponse {
+    /// Handle one raw message. A request is registered as in flight and
+    /// returned, along with the handle that serves it, to be handled on a
+    /// worker thread so the read loop stays free to receive
+    /// `notifications/cancelled` for it.
+    fn dispatch(&self, line: &str) -> Result<Option<(Self, JsonRpcRequest)>> {
+        let request = match self.parse_message(line) {
+            Ok(Incoming::Request(request)) => request,
+            Ok(Incoming::Response(response)) => {
+                self.handle_client_response(response);
+                return Ok(None);
+            }
+            Err(response) => return self.outgoing.send(&*response).map(|_| None),
+        };
+
+        if request.is_notification() {
+            self.handle_notification(&request);
+            return Ok(None);
+        }
+
+        // Register before queueing so a cancellation that arrives right away
+        // still finds the request
+        let server = self.begin_request(&request)?;
+        Ok(Some((server, request)))
+    }
+
+    /// Register `request` as in flight and return the handle that serves it.
+    fn begin_request(&self, request: &JsonRpcRequest) -> Result<Self> {
+        let id = request
+            .id
+            .as_ref()
+            .map(|id| id.to_string())
+            .unwrap_or_default();
+        let context = Arc::new(RequestContext {
+            progress_token: Mutex::new(request.params.pointer("/_meta/progressToken").cloned()),
+            ..RequestContext::default()
+        });
+        self.in_flight
+            .lock()
+            .map_err(|_| anyhow::anyhow!("In-flight requests lock poisoned"))?
+            .insert(id, context.clone());
+
+        Ok(Self {
+            request: Some(context),
+            ..self.with_current_workspace()
+        })
+    }
+
+    /// A handle on the workspace new requests currently start with.
+    fn with_current_workspace(&self) -> Self {
+        let meta_dir = match self.workspaces.lock() {
+            Ok(workspaces) => workspaces.default_dir(),
+            Err(_) => self.meta_dir.clone(),
+        };
+        Self {
+            meta_dir,
+            ..self.clone()
+        }
+    }
+
+    /// Handle a request registered with `begin_request` and send its response,
+    /// unless it was cancelled in the meantime.
+    fn finish_request(&self, request: &JsonRpcRequest) {
+        let response = self.handle_request(request);
+        if let (Some(id), Ok(mut in_flight)) = (request.id.as_ref(), self.in_flight.lock()) {
+            in_flight.remove(&id.to_string());
+        }
+
+        // Cancelled requests must not be answered
+        if let Some(response) = response.filter(|_| !self.is_cancelled()) {
+            if let Err(e) = self.outgoing.send(&response) {
+                eprintln!("Failed to send response: {e}");
+            }
+        }
+    }
+
+    /// Parse a raw message, producing the error response to send if it is not
+    /// a valid JSON-RPC message.
+    fn parse_message(&self, line: &str) -> std::result::Result<Incoming, Box<JsonRpcResponse>> {
+        let value: serde_json::Value = serde_json::from_str(line).map_err(|e| {
+            Box::new(self.error_response(None, -32700, format!("Parse error: {e}")))
+        })?;
+
+        let invalid = |e: serde_json::Error| {
+            let id = value.get("id").cloned().filter(|id| !id.is_null());
+            Box::new(self.error_response(id, -32600, format!("Invalid Request: {e}")))
+        };
+        let is_response = value.get("result").is_some() || value.get("error").is_some();
+        if value.get("method").is_none() && is_response {
+            return serde_json::from_value(value.clone())
+                .map(Incoming::Response)
+                .map_err(invalid);
+        }
+        // A null id is still a request, not a notification, so it must be answered
+        if value.get("id").is_some_and(serde_json::Value::is_null) {
+            return Err(Box::new(self.error_response(
+                None,
+                -32600,
+                "Invalid Request: id must be a string or a number".to_string(),
+            )));
+        }
+        serde_json::from_value(value.clone())
+            .map(Incoming::Request)
+            .map_err(invalid)
+    }
+
+    fn send_notification(&self, method: &str, params: serde_json::Value) -> Result<()> {
+        self.outgoing.send(&JsonRpcNotification {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params,
+        })
+    }
+
+    fn handle_request(&self, request: &JsonRpcRequest) -> Option<JsonRpcResponse> {
+        if request.is_notification() {
+            self.handle_notification(request);
+            return None;
+        }
+
         let result = match request.method.as_str() {
-            "initialize" => self.handle_initialize(),
-            "initialized" => return self.ok_response(request.id.clone(), serde_json::Value::Null),
+            "initialize" => self.handle_initialize(&request.params),
             "tools/list" => self.handle_list_tools(),
             "tools/call" => self.handle_call_tool(&request.params),
-            _ => Err(anyhow::anyhow!("Method not found: {}", request.method)),
+            "resources/list" => self.handle_list_resources(),
+            "resources/read" => self.handle_read_resource(&request.params),
+            "resources/templates/list" => self.handle_list_resource_templates(),
+            "resources/subscribe" => self.handle_subscribe(&request.params),
+            "resources/unsubscribe" => self.handle_unsubscribe(&request.params),
+            "prompts/list" => self.handle_list_prompts(),
+            "prompts/get" => self.handle_get_prompt(&request.params),
+            "logging/setLevel" => self.handle_set_level(&request.params),
+            "completion/complete" => self.handle_complete(&request.params),
+            _ => Err(RpcError::new(-32601, format!("Method not found: {}", request.method)).into()),
         };
 
-        match result {
+        Some(match result {
             Ok(value) => self.ok_response(request.id.clone(), value),
-            Err(e) => self.error_response(request.id.clone(), -32603, e.to_string()),
+            Err(e) => {
+                let rpc_error = e.downcast_ref::<RpcError>();
+                let code = rpc_error.map_or(-32603, |e| e.code);
+                let mut response = self.error_response(request.id.clone(), code, e.to_string());
+                if let Some(error) = response.error.as_mut() {
+                    error.data = rpc_error.and_then(|e| e.data.clone());
+                }
+                response
+            }
+        })
+    }
+
+    fn handle_notification(&self, request: &JsonRpcRequest) {
+        match request.method.as_str() {
+            // "initialized" is the pre-2024-11-05 spelling
+            "notifications/initialized" | "initialized" | "notifications/roots/list_changed" => {
+                self.refresh_roots();
+            }
+            "notifications/cancelled" => {
+                let request_id = request.params.get("requestId").map(|id| id.to_string());
+                let in_flight = self.in_flight.lock();
+                if let (Some(request_id), Ok(in_flight)) = (request_id, in_flight) {
+                    if let Some(context) = in_flight.get(&request_id) {
+                        context.cancel();
+                    }
+                }
+            }
+            // Unknown notifications are ignored, as JSON-RPC requires
+            _ => {}
         }
     }
 
@@ -201,13 +715,40 @@ impl McpServer {
         }
     }
 
-    fn handle_initialize(&self) -> Result<serde_json::Value> {
+    fn handle_initialize(&self, params: &serde_json::Value) -> Result<serde_json::Value> {
+        // Echo the client's revision when we speak it; otherwise offer our newest
+        // and let the client decide whether it can continue.
+        let requested = params.get("protocolVersion").and_then(|v| v.as_str());
+        let version = match requested {
+            Some(requested) => SUPPORTED_PROTOCOL_VERSIONS
+                .iter()
+                .find(|v| **v == requested)
+                .copied()
+                .unwrap_or(SUPPORTED_PROTOCOL_VERSIONS[0]),
+            None => DEFAULT_PROTOCOL_VERSION,
+        };
+        *self
+            .protocol_version
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Protocol version lock poisoned"))? = version;
+        self.client_roots.store(
+            params.pointer("/capabilities/roots").is_some(),
+            Ordering::SeqCst,
+        );
+
         let result = InitializeResult {
-            protocol_version: PROTOCOL_VERSION.to_string(),
+            protocol_version: version.to_string(),
             capabilities: ServerCapabilities {
-                tools: ToolsCapability {
+                tools: ToolsCapability { list_changed: true },
+                resources: ResourcesCapability {
+                    subscribe: true,
+                    list_changed: false,
+                },
+                prompts: PromptsCapability {
                     list_changed: false,
                 },
+                logging: LoggingCapability {},
+                completions: (version >= COMPLETIONS_VERSION).then_some(CompletionsCapability {}),
             },
             server_info: ServerInfo {
                 name: SERVER_NAME.to_string(),
@@ -218,492 +759,41 @@ impl McpServer {
     }
 
     fn handle_list_tools(&self) -> Result<serde_json::Value> {
-        let tools = vec![
-            // ================================================================
-            // Core Tools
-            // ================================================================
-            Tool {
-                name: "meta_list_projects".to_string(),
-                description: "List all projects in the meta repository".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter projects by tag"
-                        }
-                    }
-                }),
-            },
-            Tool {
-                name: "meta_exec".to_string(),
-                description: "Execute a command across all meta projects".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "command": {
-                            "type": "string",
-                            "description": "Command to execute"
-                        },
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter projects by tag"
-                        }
-                    },
-                    "required": ["command"]
-                }),
-            },
-            Tool {
-                name: "meta_get_config".to_string(),
-                description: "Get the meta repository configuration including all projects, tags, and settings".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {}
-                }),
-            },
-            Tool {
-                name: "meta_get_project_path".to_string(),
-                description: "Get the absolute path for a specific project".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "project": {
-                            "type": "string",
-                            "description": "Name of the project"
-                        }
-                    },
-                    "required": ["project"]
-                }),
-            },
-            // ================================================================
-            // Multi-Repo Git Tools (Phase 5.1)
-            // ================================================================
-            Tool {
-                name: "meta_git_status".to_string(),
-                description: "Get git status for all projects with structured output showing dirty/clean state, branch, and changes".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "project": {
-                            "type": "string",
-                            "description": "Specific project to check (optional, defaults to all)"
-                        },
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter projects by tag"
-                        }
-                    }
-                }),
-            },
-            Tool {
-                name: "meta_git_pull".to_string(),
-                description: "Pull changes from remote for all projects or filtered by tag".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter projects by tag"
-                        },
-                        "rebase": {
-                            "type": "boolean",
-                            "description": "Use rebase instead of merge (default: false)"
-                        }
-                    }
-                }),
-            },
-            Tool {
-                name: "meta_git_push".to_string(),
-                description: "Push commits to remote for all projects or filtered by tag".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter projects by tag"
-                        }
-                    }
-                }),
-            },
-            Tool {
-                name: "meta_git_fetch".to_string(),
-                description: "Fetch from remotes for all projects in parallel".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter projects by tag"
-                        }
-                    }
-                }),
-            },
-            Tool {
-                name: "meta_git_diff".to_string(),
-                description: "Get diffs across repositories showing what has changed".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "project": {
-                            "type": "string",
-                            "description": "Specific project to diff (optional, defaults to all)"
-                        },
-                        "staged": {
-                            "type": "boolean",
-                            "description": "Show only staged changes (default: false shows unstaged)"
-                        },
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter projects by tag"
-                        }
-                    }
-                }),
-            },
-            Tool {
-                name: "meta_git_branch".to_string(),
-                description: "Get branch information for all projects including current branch, tracking branch, and ahead/behind status".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter projects by tag"
-                        }
-                    }
-                }),
-            },
-            Tool {
-                name: "meta_git_add".to_string(),
-                description: "Stage files across repositories".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "project": {
-                            "type": "string",
-                            "description": "Specific project to add files in (optional, defaults to all)"
-                        },
-                        "files": {
-                            "type": "string",
-                            "description": "Files to add (default: '.' for all changed files)"
-                        },
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter projects by tag"
-                        }
-                    }
-                }),
-            },
-            Tool {
-                name: "meta_git_commit".to_string(),
-                description: "Commit staged changes across repositories with a shared message".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "message": {
-                            "type": "string",
-                            "description": "Commit message"
-                        },
-                        "project": {
-                            "type": "string",
-                            "description": "Specific project to commit in (optional, defaults to all with staged changes)"
-                        },
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter projects by tag"
-                        }
-                    },
-                    "required": ["message"]
-                }),
-            },
-            Tool {
-                name: "meta_git_checkout".to_string(),
-                description: "Checkout a branch across repositories".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "branch": {
-                            "type": "string",
-                            "description": "Branch name to checkout"
-                        },
-                        "create": {
-                            "type": "boolean",
-                            "description": "Create the branch if it doesn't exist (default: false)"
-                        },
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter projects by tag"
-                        }
-                    },
-                    "required": ["branch"]
-                }),
-            },
-            Tool {
-                name: "meta_git_multi_commit".to_string(),
-                description: "Create commits with different messages for each repository. Allows tailored commit messages per project.".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "commits": {
-                            "type": "array",
-                            "description": "Array of commit objects, each specifying a project and message",
-                            "items": {
-                                "type": "object",
-                                "properties": {
-                                    "project": {
-                                        "type": "string",
-                                        "description": "Project name (use '.' for root repo)"
-                                    },
-                                    "message": {
-                                        "type": "string",
-                                        "description": "Commit message for this project"
-                                    }
-                                },
-                                "required": ["project", "message"]
-                            }
-                        }
-                    },
-                    "required": ["commits"]
-                }),
-            },
-            // ================================================================
-            // Build/Test Orchestration Tools (Phase 5.2)
-            // ================================================================
-            Tool {
-                name: "meta_detect_build_systems".to_string(),
-                description: "Detect build systems (Cargo, npm, make, etc.) for each project".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter projects by tag"
-                        }
-                    }
-                }),
-            },
-            Tool {
-                name: "meta_run_tests".to_string(),
-                description: "Run tests across all projects using detected build systems".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter projects by tag"
-                        },
-                        "project": {
-                            "type": "string",
-                            "description": "Specific project to test"
-                        }
-                    }
-                }),
-            },
-            Tool {
-                name: "meta_build".to_string(),
-                description: "Build all projects using detected build systems".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter projects by tag"
-                        },
-                        "release": {
-                            "type": "boolean",
-                            "description": "Build in release mode (default: false)"
-                        }
-                    }
-                }),
-            },
-            Tool {
-                name: "meta_clean".to_string(),
-                description: "Clean build artifacts across all projects".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter projects by tag"
-                        }
-                    }
-                }),
-            },
-            // ================================================================
-            // Project Discovery & Analysis Tools (Phase 5.3)
-            // ================================================================
-            Tool {
-                name: "meta_search_code".to_string(),
-                description: "Search for patterns across all repositories using grep".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "pattern": {
-                            "type": "string",
-                            "description": "Search pattern (regex supported)"
-                        },
-                        "file_pattern": {
-                            "type": "string",
-                            "description": "File glob pattern to filter (e.g., '*.rs', '*.ts')"
-                        },
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter projects by tag"
-                        }
-                    },
-                    "required": ["pattern"]
-                }),
-            },
-            Tool {
-                name: "meta_get_file_tree".to_string(),
-                description: "Get file tree structure for projects".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "project": {
-                            "type": "string",
-                            "description": "Specific project to get tree for (optional, defaults to all)"
-                        },
-                        "depth": {
-                            "type": "integer",
-                            "description": "Maximum depth to traverse (default: 3)"
-                        },
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter projects by tag"
-                        }
-                    }
-                }),
-            },
-            Tool {
-                name: "meta_list_plugins".to_string(),
-                description: "List all installed meta plugins and their commands".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {}
-                }),
-            },
-            // ================================================================
-            // AI-Dominance Tools (Phase 9)
-            // ================================================================
-            Tool {
-                name: "meta_query_repos".to_string(),
-                description: "Query repositories by state/criteria using a simple DSL. Examples: 'dirty:true', 'tag:backend', 'dirty:true AND branch:main', 'modified_in:24h'".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "query": {
-                            "type": "string",
-                            "description": "Query string using DSL (e.g., 'dirty:true AND tag:backend')"
-                        }
-                    },
-                    "required": ["query"]
-                }),
-            },
-            Tool {
-                name: "meta_workspace_state".to_string(),
-                description: "Get a summary of the entire workspace state including dirty/clean counts, branches, and tags".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {}
-                }),
-            },
-            Tool {
-                name: "meta_analyze_impact".to_string(),
-                description: "Analyze what would be affected if a project changes. Returns direct and transitive dependents.".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "project": {
-                            "type": "string",
-                            "description": "Project name to analyze impact for"
-                        }
-                    },
-                    "required": ["project"]
-                }),
-            },
-            Tool {
-                name: "meta_execution_order".to_string(),
-                description: "Get topological execution order respecting dependencies. Dependencies come before dependents.".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter by tag (optional)"
-                        }
-                    }
-                }),
-            },
-            Tool {
-                name: "meta_snapshot_create".to_string(),
-                description: "Create a snapshot of the current workspace state for later rollback".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "name": {
-                            "type": "string",
-                            "description": "Name for the snapshot"
-                        },
-                        "description": {
-                            "type": "string",
-                            "description": "Optional description"
-                        }
-                    },
-                    "required": ["name"]
-                }),
-            },
-            Tool {
-                name: "meta_snapshot_list".to_string(),
-                description: "List all available workspace snapshots".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {}
-                }),
-            },
-            Tool {
-                name: "meta_snapshot_restore".to_string(),
-                description: "Restore workspace to a previously saved snapshot".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "name": {
-                            "type": "string",
-                            "description": "Name of the snapshot to restore"
-                        },
-                        "force": {
-                            "type": "boolean",
-                            "description": "Force restore even if there are uncommitted changes (default: false)"
-                        }
-                    },
-                    "required": ["name"]
-                }),
-            },
-            Tool {
-                name: "meta_batch_execute".to_string(),
-                description: "Execute a command across projects with optional atomic rollback on failure".to_string(),
-                input_schema: serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        "command": {
-                            "type": "string",
-                            "description": "Command to execute in each project"
-                        },
-                        "tag": {
-                            "type": "string",
-                            "description": "Filter projects by tag (optional)"
-                        },
-                        "atomic": {
-                            "type": "boolean",
-                            "description": "If true, automatically rollback all projects if any fail (default: false)"
-                        }
-                    },
-                    "required": ["command"]
-                }),
-            },
-        ];
+        let tools: Vec<Tool> = self.tool_registry().definitions().cloned().collect();
 
         let result = ListToolsResult { tools };
-        Ok(serde_json::to_value(result)?)
+        let mut value = serde_json::to_value(result)?;
+        if let Some(tools) = value.get_mut("tools").and_then(|t| t.as_array_mut()) {
+            for tool in tools {
+                self.strip_unsupported_fields(tool);
+            }
+        }
+        Ok(value)
+    }
+
+    /// The protocol revision negotiated with the client.
+    fn protocol_version(&self) -> &'static str {
+        self.protocol_version
+            .lock()
+            .map(|v| *v)
+            .unwrap_or(DEFAULT_PROTOCOL_VERSION)
+    }
+
+    /// Remove fields from a serialized tool or tool result that the negotiated
+    /// revision does not define, so older clients never see them.
+    ///
+    /// Revisions are ISO dates, so they order correctly as strings.
+    fn strip_unsupported_fields(&self, value: &mut serde_json::Value) {
+        let version = self.protocol_version();
+        if let Some(object) = value.as_object_mut() {
+            if version < TOOL_ANNOTATIONS_VERSION {
+                object.remove("annotations");
+            }
+            if version < STRUCTURED_CONTENT_VERSION {
+                object.remove("outputSchema");
+                object.remove("structuredContent");
+            }
+        }
     }
 
     fn handle_call_tool(&self, params: &serde_json::Value) -> Result<serde_json::Value> {
@@ -717,69 +807,80 @@ impl McpServer {
             .cloned()
             .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
 
-        let result = match name {
-            // Core tools
-            "meta_list_projects" => self.tool_list_projects(&arguments),
-            "meta_exec" => self.tool_exec(&arguments),
-            "meta_get_config" => self.tool_get_config(&arguments),
-            "meta_get_project_path" => self.tool_get_project_path(&arguments),
-            // Git tools
-            "meta_git_status" => self.tool_git_status(&arguments),
-            "meta_git_pull" => self.tool_git_pull(&arguments),
-            "meta_git_push" => self.tool_git_push(&arguments),
-            "meta_git_fetch" => self.tool_git_fetch(&arguments),
-            "meta_git_diff" => self.tool_git_diff(&arguments),
-            "meta_git_branch" => self.tool_git_branch(&arguments),
-            "meta_git_add" => self.tool_git_add(&arguments),
-            "meta_git_commit" => self.tool_git_commit(&arguments),
-            "meta_git_checkout" => self.tool_git_checkout(&arguments),
-            "meta_git_multi_commit" => self.tool_git_multi_commit(&arguments),
-            // Build/test tools
-            "meta_detect_build_systems" => self.tool_detect_build_systems(&arguments),
-            "meta_run_tests" => self.tool_run_tests(&arguments),
-            "meta_build" => self.tool_build(&arguments),
-            "meta_clean" => self.tool_clean(&arguments),
-            // Discovery tools
-            "meta_search_code" => self.tool_search_code(&arguments),
-            "meta_get_file_tree" => self.tool_get_file_tree(&arguments),
-            "meta_list_plugins" => self.tool_list_plugins(&arguments),
-            // AI-Dominance tools
-            "meta_query_repos" => self.tool_query_repos(&arguments),
-            "meta_workspace_state" => self.tool_workspace_state(&arguments),
-            "meta_analyze_impact" => self.tool_analyze_impact(&arguments),
-            "meta_execution_order" => self.tool_execution_order(&arguments),
-            "meta_snapshot_create" => self.tool_snapshot_create(&arguments),
-            "meta_snapshot_list" => self.tool_snapshot_list(&arguments),
-            "meta_snapshot_restore" => self.tool_snapshot_restore(&arguments),
-            "meta_batch_execute" => self.tool_batch_execute(&arguments),
-            _ => Err(anyhow::anyhow!("Unknown tool: {name}")),
+        let result = self
+            .for_workspace_argument(&arguments)
+            .and_then(|server| server.call_tool(name, &arguments));
+
+        let call_result = match result {
+            Ok(output) => CallToolResult {
+                content: vec![ToolContent {
+                    content_type: "text".to_string(),
+                    text: output.to_string(),
+                }],
+                structured_content: output.structured(),
+                is_error: None,
+            },
+            // Protocol-level problems such as invalid arguments are JSON-RPC
+            // errors rather than failed tool results
+            Err(e) if e.is::<RpcError>() => return Err(e),
+            Err(e) => CallToolResult {
+                content: vec![ToolContent {
+                    content_type: "text".to_string(),
+                    text: e.to_string(),
+                }],
+                structured_content: None,
+                is_error: Some(true),
+            },
         };
 
-        match result {
-            Ok(text) => {
-                let call_result = CallToolResult {
-                    content: vec![ToolContent {
-                        content_type: "text".to_string(),
-                        text,
-                    }],
-                    is_error: None,
-                };
-                Ok(serde_json::to_value(call_result)?)
-            }
-            Err(e) => {
-                let call_result = CallToolResult {
-                    content: vec![ToolContent {
-                        content_type: "text".to_string(),
-                        text: e.to_string(),
-                    }],
-                    is_error: Some(true),
-                };
-                Ok(serde_json::to_value(call_result)?)
+        let mut value = serde_json::to_value(call_result)?;
+        self.strip_unsupported_fields(&mut value);
+        Ok(value)
+    }
+
+    /// Run tool `name` on this handle's workspace. Arguments that do not
+    /// match the tool's input schema are refused with `-32602`.
+    fn call_tool(&self, name: &str, arguments: &serde_json::Value) -> Result<ToolOutput> {
+        // Without readable settings the workspace's guardrails are unknown
+        let config = self.server_config();
+        if let Some(error) = &config.error {
+            anyhow::bail!("Tool calls are refused until the server settings are fixed: {error}");
+        }
+
+        let registry = self.tool_registry();
+        let tool = match (registry.get(name), registry.withheld(name)) {
+            (Some(tool), _) => tool,
+            (None, Some(reason)) => {
+                return Err(RpcError::new(-32602, format!("Tool '{name}' {reason}")).into())
             }
+            (None, None) => anyhow::bail!("Unknown tool: {name}"),
+        };
+
+        let violations = validation::violations(&tool.definition().input_schema, arguments);
+        if !violations.is_empty() {
+            return Err(RpcError::new(
+                -32602,
+                format!("Invalid arguments for {name}: {}", violations.join("; ")),
+            )
+            .with_data(serde_json::json!({ "violations": violations }))
+            .into());
         }
+
+        let server = Self {
+            timeout: Some(process::tool_timeout(name, arguments, &config)),
+            output_limit: config.max_output_bytes,
+            ..self.clone()
+        };
+        // Calls that change projects take turns on each project
+        let _projects = if registry.is_read_only(name) {
+            None
+        } else {
+            Some(server.lock_projects(server.selected_project_paths(arguments))?)
+        };
+        tool.call(&server, arguments)
     }
 
-    fn tool_list_projects(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_list_projects(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -810,49 +911,56 @@ impl McpServer {
             }
         }
 
-        Ok(output)
+        Ok(ToolOutput::Text(output))
     }
 
-    fn tool_git_status(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_git_status(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("No meta repository found"))?;
 
-        let project_filter = args.get("project").and_then(|v| v.as_str());
+        let projects = self.load_projects_with_root(meta_dir)?;
+        let selected = selected_projects(&projects, args)?;
 
-        // Run meta git status with --json for structured output
-        let mut cmd = Command::new("meta");
-        cmd.arg("--json").arg("git").arg("status");
-        cmd.current_dir(meta_dir);
-
-        let output = cmd.output().context("Failed to execute meta git status")?;
+        let results = self.for_each_project(&selected, self.concurrency(args), |project| {
+            let project_path = meta_dir.join(&project.path);
+            if !project_path.exists() {
+                self.log_skipped(&project.name, "directory does not exist");
+                return None;
+            }
 
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-
-            // If project filter specified, parse JSON and filter
-            if let Some(project) = project_filter {
-                if let Ok(json) = serde_json::from_str::<serde_json::Value>(&stdout) {
-                    if let Some(results) = json.get("results").and_then(|r| r.as_array()) {
-                        for result in results {
-                            if result.get("project").and_then(|p| p.as_str()) == Some(project) {
-                                return Ok(serde_json::to_string_pretty(result)?);
-                            }
-                        }
-                        return Err(anyhow::anyhow!("Project '{project}' not found"));
-                    }
+            let output = self.run_command(
+                Command::new("git")
+                    .args(["status", "--porcelain"])
+                    .current_dir(&project_path),
+            );
+            Some(match output {
+                Ok(out) if out.status.success() => {
+                    let changes: Vec<String> = String::from_utf8_lossy(&out.stdout)
+                        .lines()
+                        .map(str::to_string)
+                        .collect();
+                    serde_json::json!({
+                        "project": project.name,
+                        "branch": git_utils::current_branch(&project_path),
+                        "dirty": !changes.is_empty(),
+                        "changes": changes
+                    })
                 }
-            }
+                Ok(out) => serde_json::json!({
+                    "project": project.name,
+                    "error": String::from_utf8_lossy(&out.stderr).trim().to_string()
+                }),
+                Err(e) => failed_project(&project.name, &e),
+            })
+        });
+        let results: Vec<serde_json::Value> = results.into_iter().flatten().collect();
 
-            Ok(stdout.to_string())
-        } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            Err(anyhow::anyhow!("meta git status failed: {stderr}"))
-        }
+        ToolOutput::json(&results)
     }
 
-    fn tool_exec(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_exec(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -863,55 +971,44 @@ impl McpServer {
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Missing 'command' argument"))?;
 
-        let mut cmd = Command::new("meta");
-        cmd.arg("--json");
-
-        if let Some(tag) = args.get("tag").and_then(|v| v.as_str()) {
-            cmd.arg("--tag").arg(tag);
+        // Split command into parts; no shell and no `meta` is involved, so
+        // this runs a program, not a meta subcommand or plugin
+        let words: Vec<&str> = command.split_whitespace().collect();
+        if words.is_empty() {
+            anyhow::bail!("Empty 'command' argument");
         }
 
-        // Split command into parts
-        for part in command.split_whitespace() {
-            cmd.arg(part);
-        }
-
-        cmd.current_dir(meta_dir);
-
-        let output = cmd.output().context("Failed to execute meta command")?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+        let projects = self.load_projects_with_root(meta_dir)?;
+        let selected = selected_projects(&projects, args)?;
+        let results = self.for_each_project(&selected, self.concurrency(args), |project| {
+            self.run_in_project(meta_dir, project, &words)
+        });
 
-        if output.status.success() {
-            Ok(stdout.to_string())
-        } else {
-            Err(anyhow::anyhow!("Command failed:\n{stdout}\n{stderr}"))
-        }
+        ToolOutput::json(&results)
     }
 
     // ========================================================================
     // Core Tools
     // ========================================================================
 
-    fn tool_get_config(&self, _args: &serde_json::Value) -> Result<String> {
+    fn tool_get_config(&self, _args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("No meta repository found"))?;
 
         // Find and return the raw config
-        for name in &[".meta", ".meta.yaml", ".meta.yml"] {
-            let path = meta_dir.join(name);
-            if path.exists() {
-                let content = std::fs::read_to_string(&path)?;
-                return Ok(format!("Config file: {}\n\n{}", path.display(), content));
-            }
-        }
-
-        Err(anyhow::anyhow!("No meta config found"))
+        let path =
+            Self::config_file(meta_dir).ok_or_else(|| anyhow::anyhow!("No meta config found"))?;
+        let content = std::fs::read_to_string(&path)?;
+        Ok(ToolOutput::Text(format!(
+            "Config file: {}\n\n{}",
+            path.display(),
+            content
+        )))
     }
 
-    fn tool_get_project_path(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_get_project_path(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -927,12 +1024,11 @@ impl McpServer {
         for project in projects {
             if project.name == project_name {
                 let full_path = meta_dir.join(&project.path);
-                return Ok(serde_json::json!({
+                return Ok(ToolOutput::Json(serde_json::json!({
                     "project": project.name,
                     "path": full_path.display().to_string(),
                     "exists": full_path.exists()
-                })
-                .to_string());
+                })));
             }
         }
 
@@ -943,100 +1039,27 @@ impl McpServer {
     // Git Tools
     // ========================================================================
 
-    fn tool_git_pull(&self, args: &serde_json::Value) -> Result<String> {
-        let meta_dir = self
-            .meta_dir
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No meta repository found"))?;
-
-        let mut cmd = Command::new("meta");
-        cmd.arg("--json");
-
-        if let Some(tag) = args.get("tag").and_then(|v| v.as_str()) {
-            cmd.arg("--tag").arg(tag);
-        }
-
-        cmd.arg("git").arg("pull");
-
+    fn tool_git_pull(&self, args: &serde_json::Value) -> Result<ToolOutput> {
+        let mut command = vec!["git", "pull"];
         if args
             .get("rebase")
             .and_then(|v| v.as_bool())
             .unwrap_or(false)
         {
-            cmd.arg("--rebase");
-        }
-
-        cmd.current_dir(meta_dir);
-
-        let output = cmd.output().context("Failed to execute meta git pull")?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
-
-        if output.status.success() {
-            Ok(stdout.to_string())
-        } else {
-            Err(anyhow::anyhow!("git pull failed:\n{stdout}\n{stderr}"))
+            command.push("--rebase");
         }
+        self.git_in_projects(args, &command)
     }
 
-    fn tool_git_push(&self, args: &serde_json::Value) -> Result<String> {
-        let meta_dir = self
-            .meta_dir
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No meta repository found"))?;
-
-        let mut cmd = Command::new("meta");
-        cmd.arg("--json");
-
-        if let Some(tag) = args.get("tag").and_then(|v| v.as_str()) {
-            cmd.arg("--tag").arg(tag);
-        }
-
-        cmd.arg("git").arg("push");
-        cmd.current_dir(meta_dir);
-
-        let output = cmd.output().context("Failed to execute meta git push")?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
-
-        if output.status.success() {
-            Ok(stdout.to_string())
-        } else {
-            Err(anyhow::anyhow!("git push failed:\n{stdout}\n{stderr}"))
-        }
+    fn tool_git_push(&self, args: &serde_json::Value) -> Result<ToolOutput> {
+        self.git_in_projects(args, &["git", "push"])
     }
 
-    fn tool_git_fetch(&self, args: &serde_json::Value) -> Result<String> {
-        let meta_dir = self
-            .meta_dir
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No meta repository found"))?;
-
-        let mut cmd = Command::new("meta");
-        cmd.arg("--json");
-
-        if let Some(tag) = args.get("tag").and_then(|v| v.as_str()) {
-            cmd.arg("--tag").arg(tag);
-        }
-
-        cmd.arg("git").arg("fetch");
-        cmd.current_dir(meta_dir);
-
-        let output = cmd.output().context("Failed to execute meta git fetch")?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
-
-        if output.status.success() {
-            Ok(stdout.to_string())
-        } else {
-            Err(anyhow::anyhow!("git fetch failed:\n{stdout}\n{stderr}"))
-        }
+    fn tool_git_fetch(&self, args: &serde_json::Value) -> Result<ToolOutput> {
+        self.git_in_projects(args, &["git", "fetch"])
     }
 
-    fn tool_git_diff(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_git_diff(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -1063,12 +1086,11 @@ impl McpServer {
             })
             .collect();
 
-        let mut results = Vec::new();
-
-        for project in filtered {
+        let results = self.for_each_project(&filtered, self.concurrency(args), |project| {
             let project_path = meta_dir.join(&project.path);
             if !project_path.exists() {
-                continue;
+                self.log_skipped(&project.name, "directory does not exist");
+                return None;
             }
 
             let mut cmd = Command::new("git");
@@ -1078,21 +1100,25 @@ impl McpServer {
             }
             cmd.current_dir(&project_path);
 
-            let output = cmd.output()?;
+            let output = match self.run_command(&mut cmd) {
+                Ok(output) => output,
+                Err(e) => return Some(failed_project(&project.name, &e)),
+            };
             let diff = String::from_utf8_lossy(&output.stdout);
 
-            if !diff.is_empty() {
-                results.push(serde_json::json!({
+            (!diff.is_empty()).then(|| {
+                serde_json::json!({
                     "project": project.name,
                     "diff": diff.to_string()
-                }));
-            }
-        }
+                })
+            })
+        });
+        let results: Vec<serde_json::Value> = results.into_iter().flatten().collect();
 
-        Ok(serde_json::to_string_pretty(&results)?)
+        ToolOutput::json(&results)
     }
 
-    fn tool_git_branch(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_git_branch(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -1110,95 +1136,84 @@ impl McpServer {
             projects.iter().collect()
         };
 
-        let mut results = Vec::new();
-
-        for project in filtered {
+        let results = self.for_each_project(&filtered, self.concurrency(args), |project| {
             let project_path = meta_dir.join(&project.path);
             if !project_path.exists() {
-                continue;
+                self.log_skipped(&project.name, "directory does not exist");
+                return None;
             }
 
-            // Get current branch
-            let current_branch =
-                git_utils::current_branch(&project_path).unwrap_or_else(|| "HEAD".to_string());
+            Some(
+                self.project_branch(project, &project_path)
+                    .unwrap_or_else(|e| failed_project(&project.name, &e)),
+            )
+        });
+        let results: Vec<serde_json::Value> = results.into_iter().flatten().collect();
+
+        ToolOutput::json(&results)
+    }
+
+    /// Current branch, tracking branch and ahead/behind counts of a project.
+    fn project_branch(
+        &self,
+        project: &ProjectInfo,
+        project_path: &std::path::Path,
+    ) -> Result<serde_json::Value> {
+        // Get current branch
+        let current_branch =
+            git_utils::current_branch(project_path).unwrap_or_else(|| "HEAD".to_string());
 
-            // Get tracking branch info
-            let tracking_output = Command::new("git")
+        // Get tracking branch info
+        let tracking_output = self.run_command(
+            Command::new("git")
                 .args([
                     "for-each-ref",
                     "--format=%(upstream:short)",
                     &format!("refs/heads/{current_branch}"),
                 ])
-                .current_dir(&project_path)
-                .output()?;
-            let tracking_branch = String::from_utf8_lossy(&tracking_output.stdout)
-                .trim()
-                .to_string();
-
-            // Get ahead/behind counts
-            let mut ahead = 0;
-            let mut behind = 0;
-            if !tracking_branch.is_empty() {
-                let ahead_behind = Command::new("git")
+                .current_dir(project_path),
+        )?;
+        let tracking_branch = String::from_utf8_lossy(&tracking_output.stdout)
+            .trim()
+            .to_string();
+
+        // Get ahead/behind counts
+        let mut ahead = 0;
+        let mut behind = 0;
+        if !tracking_branch.is_empty() {
+            let ahead_behind = self.run_command(
+                Command::new("git")
                     .args([
                         "rev-list",
                         "--left-right",
                         "--count",
                         &format!("{current_branch}...{tracking_branch}"),
                     ])
-                    .current_dir(&project_path)
-                    .output()?;
-                let counts = String::from_utf8_lossy(&ahead_behind.stdout);
-                let parts: Vec<&str> = counts.trim().split('\t').collect();
-                if parts.len() == 2 {
-                    ahead = parts[0].parse().unwrap_or(0);
-                    behind = parts[1].parse().unwrap_or(0);
-                }
+                    .current_dir(project_path),
+            )?;
+            let counts = String::from_utf8_lossy(&ahead_behind.stdout);
+            let parts: Vec<&str> = counts.trim().split('\t').collect();
+            if parts.len() == 2 {
+                ahead = parts[0].parse().unwrap_or(0);
+                behind = parts[1].parse().unwrap_or(0);
             }
-
-            results.push(serde_json::json!({
-                "project": project.name,
-                "branch": current_branch,
-                "tracking": if tracking_branch.is_empty() { None } else { Some(&tracking_branch) },
-                "ahead": ahead,
-                "behind": behind
-            }));
         }
 
-        Ok(serde_json::to_string_pretty(&results)?)
+        Ok(serde_json::json!({
+            "project": project.name,
+            "branch": current_branch,
+            "tracking": if tracking_branch.is_empty() { None } else { Some(&tracking_branch) },
+            "ahead": ahead,
+            "behind": behind
+        }))
     }
 
-    fn tool_git_add(&self, args: &serde_json::Value) -> Result<String> {
-        let meta_dir = self
-            .meta_dir
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No meta repository found"))?;
-
+    fn tool_git_add(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let files = args.get("files").and_then(|v| v.as_str()).unwrap_or(".");
-
-        let mut cmd = Command::new("meta");
-        cmd.arg("--json");
-
-        if let Some(tag) = args.get("tag").and_then(|v| v.as_str()) {
-            cmd.arg("--tag").arg(tag);
-        }
-
-        cmd.arg("git").arg("add").arg(files);
-        cmd.current_dir(meta_dir);
-
-        let output = cmd.output().context("Failed to execute meta git add")?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
-
-        if output.status.success() {
-            Ok(format!("Staged files: {files}\n{stdout}"))
-        } else {
-            Err(anyhow::anyhow!("git add failed:\n{stdout}\n{stderr}"))
-        }
+        self.git_in_projects(args, &["git", "add", files])
     }
 
-    fn tool_git_commit(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_git_commit(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -1209,29 +1224,36 @@ impl McpServer {
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Missing 'message' argument"))?;
 
-        let mut cmd = Command::new("meta");
-        cmd.arg("--json");
-
-        if let Some(tag) = args.get("tag").and_then(|v| v.as_str()) {
-            cmd.arg("--tag").arg(tag);
-        }
-
-        cmd.arg("git").arg("commit").arg("-m").arg(message);
-        cmd.current_dir(meta_dir);
-
-        let output = cmd.output().context("Failed to execute meta git commit")?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+        let projects = self.load_projects_with_root(meta_dir)?;
+        let selected = selected_projects(&projects, args)?;
+        let results = self.for_each_project(&selected, self.concurrency(args), |project| {
+            // Projects with nothing staged are left alone rather than failing,
+            // but still reported
+            let project_path = meta_dir.join(&project.path);
+            if project_path.exists() {
+                let nothing_staged = self
+                    .run_command(
+                        Command::new("git")
+                            .args(["diff", "--cached", "--quiet"])
+                            .current_dir(&project_path),
+                    )
+                    .is_ok_and(|out| out.status.success());
+                if nothing_staged {
+                    self.log_skipped(&project.name, "nothing staged");
+                    return serde_json::json!({
+                        "project": project.name,
+                        "success": true,
+                        "skipped": "nothing staged"
+                    });
+                }
+            }
+            self.run_in_project(meta_dir, project, &["git", "commit", "-m", message])
+        });
 
-        if output.status.success() {
-            Ok(stdout.to_string())
-        } else {
-            Err(anyhow::anyhow!("git commit failed:\n{stdout}\n{stderr}"))
-        }
+        ToolOutput::json(&results)
     }
 
-    fn tool_git_multi_commit(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_git_multi_commit(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -1285,13 +1307,14 @@ impl McpServer {
             }
 
             // Execute git commit for this project
-            let output = Command::new("git")
-                .arg("-C")
-                .arg(&project_path)
-                .arg("commit")
-                .arg("-m")
-                .arg(message)
-                .output();
+            let output = self.run_command(
+                Command::new("git")
+                    .arg("-C")
+                    .arg(&project_path)
+                    .arg("commit")
+                    .arg("-m")
+                    .arg(message),
+            );
 
             match output {
                 Ok(out) => {
@@ -1335,15 +1358,10 @@ impl McpServer {
             }
         });
 
-        Ok(serde_json::to_string_pretty(&output)?)
+        ToolOutput::json(&output)
     }
 
-    fn tool_git_checkout(&self, args: &serde_json::Value) -> Result<String> {
-        let meta_dir = self
-            .meta_dir
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No meta repository found"))?;
-
+    fn tool_git_checkout(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let branch = args
             .get("branch")
             .and_then(|v| v.as_str())
@@ -1354,39 +1372,19 @@ impl McpServer {
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
 
-        let mut cmd = Command::new("meta");
-        cmd.arg("--json");
-
-        if let Some(tag) = args.get("tag").and_then(|v| v.as_str()) {
-            cmd.arg("--tag").arg(tag);
-        }
-
-        cmd.arg("git").arg("checkout");
+        let mut command = vec!["git", "checkout"];
         if create {
-            cmd.arg("-b");
-        }
-        cmd.arg(branch);
-        cmd.current_dir(meta_dir);
-
-        let output = cmd
-            .output()
-            .context("Failed to execute meta git checkout")?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
-
-        if output.status.success() {
-            Ok(stdout.to_string())
-        } else {
-            Err(anyhow::anyhow!("git checkout failed:\n{stdout}\n{stderr}"))
+            command.push("-b");
         }
+        command.push(branch);
+        self.git_in_projects(args, &command)
     }
 
     // ========================================================================
     // Build/Test Tools
     // ========================================================================
 
-    fn tool_detect_build_systems(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_detect_build_systems(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -1409,6 +1407,7 @@ impl McpServer {
         for project in filtered {
             let project_path = meta_dir.join(&project.path);
             if !project_path.exists() {
+                self.log_skipped(&project.name, "directory does not exist");
                 continue;
             }
 
@@ -1448,10 +1447,10 @@ impl McpServer {
             }));
         }
 
-        Ok(serde_json::to_string_pretty(&results)?)
+        ToolOutput::json(&results)
     }
 
-    fn tool_run_tests(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_run_tests(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -1474,55 +1473,58 @@ impl McpServer {
             })
             .collect();
 
-        let mut results = Vec::new();
-
-        for project in filtered {
+        let config = self.server_config();
+        let results = self.for_each_project(&filtered, self.concurrency(args), |project| {
             let project_path = meta_dir.join(&project.path);
             if !project_path.exists() {
-                continue;
+                self.log_skipped(&project.name, "directory does not exist");
+                return None;
             }
 
-            let (cmd_name, cmd_args): (&str, Vec<&str>) =
-                if project_path.join("Cargo.toml").exists() {
-                    ("cargo", vec!["test"])
-                } else if project_path.join("package.json").exists() {
-                    ("npm", vec!["test"])
-                } else if project_path.join("go.mod").exists() {
-                    ("go", vec!["test", "./..."])
-                } else if project_path.join("Makefile").exists() {
-                    ("make", vec!["test"])
-                } else {
-                    continue; // No recognized test command
-                };
+            let configured = config.test_command(project);
+            let (cmd_name, cmd_args): (&str, Vec<&str>) = if let Some(command) = configured {
+                ("sh", vec!["-c", command])
+            } else if project_path.join("Cargo.toml").exists() {
+                ("cargo", vec!["test"])
+            } else if project_path.join("package.json").exists() {
+                ("npm", vec!["test"])
+            } else if project_path.join("go.mod").exists() {
+                ("go", vec!["test", "./..."])
+            } else if project_path.join("Makefile").exists() {
+                ("make", vec!["test"])
+            } else {
+                self.log_skipped(&project.name, "no recognized test command");
+                return None;
+            };
 
-            let output = Command::new(cmd_name)
-                .args(&cmd_args)
-                .current_dir(&project_path)
-                .output();
+            let output = self.run_command(
+                Command::new(cmd_name)
+                    .args(&cmd_args)
+                    .current_dir(&project_path),
+            );
 
-            match output {
-                Ok(out) => {
-                    results.push(serde_json::json!({
-                        "project": project.name,
-                        "command": format!("{} {}", cmd_name, cmd_args.join(" ")),
-                        "success": out.status.success(),
-                        "stdout": String::from_utf8_lossy(&out.stdout).to_string(),
-                        "stderr": String::from_utf8_lossy(&out.stderr).to_string()
-                    }));
-                }
-                Err(e) => {
-                    results.push(serde_json::json!({
-                        "project": project.name,
-                        "error": e.to_string()
-                    }));
-                }
-            }
-        }
+            let command = match configured {
+                Some(command) => command.to_string(),
+                None => format!("{} {}", cmd_name, cmd_args.join(" ")),
+            };
 
-        Ok(serde_json::to_string_pretty(&results)?)
+            Some(match output {
+                Ok(out) => serde_json::json!({
+                    "project": project.name,
+                    "command": command,
+                    "success": out.status.success(),
+                    "stdout": String::from_utf8_lossy(&out.stdout).to_string(),
+                    "stderr": String::from_utf8_lossy(&out.stderr).to_string()
+                }),
+                Err(e) => failed_project(&project.name, &e),
+            })
+        });
+        let results: Vec<serde_json::Value> = results.into_iter().flatten().collect();
+
+        ToolOutput::json(&results)
     }
 
-    fn tool_build(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_build(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -1533,35 +1535,29 @@ impl McpServer {
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
 
-        let mut cmd = Command::new("meta");
-        cmd.arg("--json");
-
-        if let Some(tag) = args.get("tag").and_then(|v| v.as_str()) {
-            cmd.arg("--tag").arg(tag);
-        }
-
-        // Use meta exec to run build commands
-        if release {
-            cmd.args(["exec", "--", "cargo", "build", "--release"]);
-        } else {
-            cmd.args(["exec", "--", "cargo", "build"]);
-        }
-
-        cmd.current_dir(meta_dir);
-
-        let output = cmd.output().context("Failed to execute meta build")?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+        let projects = self.load_projects_with_root(meta_dir)?;
+        let selected = selected_projects(&projects, args)?;
+        let config = self.server_config();
+        let results = self.for_each_project(&selected, self.concurrency(args), |project| {
+            if let Some(command) = config.build_command(project) {
+                let mut result = self.run_in_project(meta_dir, project, &["sh", "-c", command]);
+                result["command"] = command.into();
+                return result;
+            }
+            let command: &[&str] = if release {
+                &["cargo", "build", "--release"]
+            } else {
+                &["cargo", "build"]
+            };
+            let mut result = self.run_in_project(meta_dir, project, command);
+            result["command"] = command.join(" ").into();
+            result
+        });
 
-        if output.status.success() {
-            Ok(stdout.to_string())
-        } else {
-            Err(anyhow::anyhow!("build failed:\n{stdout}\n{stderr}"))
-        }
+        ToolOutput::json(&results)
     }
 
-    fn tool_clean(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_clean(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -1579,58 +1575,55 @@ impl McpServer {
             projects.iter().collect()
         };
 
-        let mut results = Vec::new();
-
-        for project in filtered {
+        let results = self.for_each_project(&filtered, self.concurrency(args), |project| {
             let project_path = meta_dir.join(&project.path);
             if !project_path.exists() {
-                continue;
+                self.log_skipped(&project.name, "directory does not exist");
+                return None;
             }
 
             let (cmd_name, cmd_args): (&str, Vec<&str>) =
                 if project_path.join("Cargo.toml").exists() {
                     ("cargo", vec!["clean"])
                 } else if project_path.join("package.json").exists() {
-                    // For npm projects, we'd typically remove node_modules
-                    continue; // Skip npm for now as rm -rf is dangerous
+                    // For npm projects, we'd typically remove node_modules, but
+                    // rm -rf is too dangerous to run on the user's behalf
+                    self.log_skipped(&project.name, "npm projects are not cleaned");
+                    return None;
                 } else if project_path.join("go.mod").exists() {
                     ("go", vec!["clean"])
                 } else if project_path.join("Makefile").exists() {
                     ("make", vec!["clean"])
                 } else {
-                    continue;
+                    self.log_skipped(&project.name, "no recognized clean command");
+                    return None;
                 };
 
-            let output = Command::new(cmd_name)
-                .args(&cmd_args)
-                .current_dir(&project_path)
-                .output();
+            let output = self.run_command(
+                Command::new(cmd_name)
+                    .args(&cmd_args)
+                    .current_dir(&project_path),
+            );
 
-            match output {
-                Ok(out) => {
-                    results.push(serde_json::json!({
-                        "project": project.name,
-                        "command": format!("{} {}", cmd_name, cmd_args.join(" ")),
-                        "success": out.status.success()
-                    }));
-                }
-                Err(e) => {
-                    results.push(serde_json::json!({
-                        "project": project.name,
-                        "error": e.to_string()
-                    }));
-                }
-            }
-        }
+            Some(match output {
+                Ok(out) => serde_json::json!({
+                    "project": project.name,
+                    "command": format!("{} {}", cmd_name, cmd_args.join(" ")),
+                    "success": out.status.success()
+                }),
+                Err(e) => failed_project(&project.name, &e),
+            })
+        });
+        let results: Vec<serde_json::Value> = results.into_iter().flatten().collect();
 
-        Ok(serde_json::to_string_pretty(&results)?)
+        ToolOutput::json(&results)
     }
 
     // ========================================================================
     // Discovery Tools
     // ========================================================================
 
-    fn tool_search_code(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_search_code(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -1655,12 +1648,11 @@ impl McpServer {
             projects.iter().collect()
         };
 
-        let mut results = Vec::new();
-
-        for project in filtered {
+        let results = self.for_each_project(&filtered, self.concurrency(args), |project| {
             let project_path = meta_dir.join(&project.path);
             if !project_path.exists() {
-                continue;
+                self.log_skipped(&project.name, "directory does not exist");
+                return None;
             }
 
             let mut cmd = Command::new("grep");
@@ -1673,26 +1665,27 @@ impl McpServer {
             cmd.arg(pattern);
             cmd.current_dir(&project_path);
 
-            let output = cmd.output();
+            let output = self.run_command(&mut cmd);
 
             match output {
                 Ok(out) => {
                     let matches = String::from_utf8_lossy(&out.stdout);
-                    if !matches.is_empty() {
-                        results.push(serde_json::json!({
+                    (!matches.is_empty()).then(|| {
+                        serde_json::json!({
                             "project": project.name,
                             "matches": matches.lines().take(50).collect::<Vec<_>>()
-                        }));
-                    }
+                        })
+                    })
                 }
-                Err(_) => continue,
+                Err(e) => Some(failed_project(&project.name, &e)),
             }
-        }
+        });
+        let results: Vec<serde_json::Value> = results.into_iter().flatten().collect();
 
-        Ok(serde_json::to_string_pretty(&results)?)
+        ToolOutput::json(&results)
     }
 
-    fn tool_get_file_tree(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_get_file_tree(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -1722,6 +1715,7 @@ impl McpServer {
         for project in filtered {
             let project_path = meta_dir.join(&project.path);
             if !project_path.exists() {
+                self.log_skipped(&project.name, "directory does not exist");
                 continue;
             }
 
@@ -1732,7 +1726,7 @@ impl McpServer {
             }));
         }
 
-        Ok(serde_json::to_string_pretty(&results)?)
+        ToolOutput::json(&results)
     }
 
     fn build_file_tree(
@@ -1785,35 +1779,15 @@ impl McpServer {
         Ok(serde_json::Value::Array(children))
     }
 
-    fn tool_list_plugins(&self, _args: &serde_json::Value) -> Result<String> {
-        let meta_dir = self
-            .meta_dir
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No meta repository found"))?;
-
-        let mut cmd = Command::new("meta");
-        cmd.arg("--json").arg("plugins").arg("list");
-        cmd.current_dir(meta_dir);
-
-        let output = cmd.output().context("Failed to list plugins")?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-
-        if output.status.success() {
-            Ok(stdout.to_string())
-        } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            Err(anyhow::anyhow!(
-                "Failed to list plugins:\n{stdout}\n{stderr}"
-            ))
-        }
+    fn tool_list_plugins(&self, _args: &serde_json::Value) -> Result<ToolOutput> {
+        ToolOutput::json(&self.plugin_listing()?)
     }
 
     // ========================================================================
     // AI-Dominance Tools (Phase 9)
     // ========================================================================
 
-    fn tool_query_repos(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_query_repos(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -1827,50 +1801,44 @@ impl McpServer {
         let query = Query::parse(query_str)?;
         let projects = self.load_projects(meta_dir)?;
         let mut matching = Vec::new();
+        let mut failed = Vec::new();
 
         for project in &projects {
             let project_path = meta_dir.join(&project.path);
             if !project_path.exists() {
+                self.log_skipped(&project.name, "directory does not exist");
                 continue;
             }
 
-            let state = RepoState::collect(&project.name, &project_path, &project.tags)?;
-            if state.matches(&query) {
-                matching.push(state);
+            match RepoState::collect(&project.name, &project_path, &project.tags) {
+                Ok(state) if state.matches(&query) => matching.push(state),
+                Ok(_) => {}
+                Err(e) => failed.push(failed_project(&project.name, &anyhow::anyhow!(e))),
             }
         }
 
-        Ok(serde_json::to_string_pretty(&serde_json::json!({
+        let mut output = serde_json::json!({
             "query": query_str,
             "matches": matching.len(),
             "projects": matching
-        }))?)
+        });
+        if !failed.is_empty() {
+            output["failed"] = failed.into();
+        }
+        Ok(ToolOutput::Json(output))
     }
 
-    fn tool_workspace_state(&self, _args: &serde_json::Value) -> Result<String> {
+    fn tool_workspace_state(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("No meta repository found"))?;
 
-        let projects = self.load_projects(meta_dir)?;
-        let mut repo_states = Vec::new();
-
-        for project in &projects {
-            let project_path = meta_dir.join(&project.path);
-            if !project_path.exists() {
-                continue;
-            }
-            if let Ok(state) = RepoState::collect(&project.name, &project_path, &project.tags) {
-                repo_states.push(state);
-            }
-        }
-
-        let workspace_state = WorkspaceState::from_repos(&repo_states);
-        Ok(serde_json::to_string_pretty(&workspace_state)?)
+        let workspace_state = self.workspace_state(meta_dir, self.concurrency(args))?;
+        Ok(ToolOutput::Json(workspace_state))
     }
 
-    fn tool_analyze_impact(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_analyze_impact(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -1885,10 +1853,10 @@ impl McpServer {
         let graph = dependency_graph::DependencyGraph::build(projects)?;
         let impact = graph.analyze_impact(project_name);
 
-        Ok(serde_json::to_string_pretty(&impact)?)
+        ToolOutput::json(&impact)
     }
 
-    fn tool_execution_order(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_execution_order(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -1901,14 +1869,14 @@ impl McpServer {
         let tags: Vec<String> = tag_filter.map(|t| vec![t.to_string()]).unwrap_or_default();
         let order = graph.execution_order_filtered(&tags)?;
 
-        Ok(serde_json::to_string_pretty(&serde_json::json!({
+        ToolOutput::json(&serde_json::json!({
             "execution_order": order,
             "count": order.len(),
             "tag_filter": tag_filter
-        }))?)
+        }))
     }
 
-    fn tool_snapshot_create(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_snapshot_create(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -1927,6 +1895,7 @@ impl McpServer {
         for project in &projects {
             let project_path = meta_dir.join(&project.path);
             if !project_path.exists() || !project_path.join(".git").exists() {
+                self.log_skipped(&project.name, "not a git checkout");
                 continue;
             }
 
@@ -1955,27 +1924,27 @@ impl McpServer {
         });
 
         // Save snapshot
-        let snapshots_dir = meta_dir.join(".meta-snapshots");
+        let snapshots_dir = self.snapshots_dir(meta_dir);
         std::fs::create_dir_all(&snapshots_dir)?;
         let filename = format!("{}.json", name.replace(['/', '\\', ' '], "_"));
         let snapshot_path = snapshots_dir.join(&filename);
         std::fs::write(&snapshot_path, serde_json::to_string_pretty(&snapshot)?)?;
 
-        Ok(serde_json::to_string_pretty(&serde_json::json!({
+        ToolOutput::json(&serde_json::json!({
             "status": "created",
             "name": name,
             "path": snapshot_path.to_string_lossy(),
             "projects_count": project_snapshots.len()
-        }))?)
+        }))
     }
 
-    fn tool_snapshot_list(&self, _args: &serde_json::Value) -> Result<String> {
+    fn tool_snapshot_list(&self, _args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("No meta repository found"))?;
 
-        let snapshots_dir = meta_dir.join(".meta-snapshots");
+        let snapshots_dir = self.snapshots_dir(meta_dir);
         let mut snapshots = Vec::new();
 
         if snapshots_dir.exists() {
@@ -1997,13 +1966,13 @@ impl McpServer {
             }
         }
 
-        Ok(serde_json::to_string_pretty(&serde_json::json!({
+        ToolOutput::json(&serde_json::json!({
             "snapshots": snapshots,
             "count": snapshots.len()
-        }))?)
+        }))
     }
 
-    fn tool_snapshot_restore(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_snapshot_restore(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -2017,7 +1986,7 @@ impl McpServer {
         let force = args.get("force").and_then(|v| v.as_bool()).unwrap_or(false);
 
         // Load snapshot
-        let snapshots_dir = meta_dir.join(".meta-snapshots");
+        let snapshots_dir = self.snapshots_dir(meta_dir);
         let filename = format!("{}.json", name.replace(['/', '\\', ' '], "_"));
         let snapshot_path = snapshots_dir.join(&filename);
 
@@ -2035,12 +2004,14 @@ impl McpServer {
 
         let mut restored = Vec::new();
         let mut failed = Vec::new();
+        let progress = self.progress(projects.len());
 
-        for project in projects {
+        for (done, project) in projects.iter().enumerate() {
             let proj_name = project
                 .get("name")
                 .and_then(|v| v.as_str())
                 .unwrap_or("unknown");
+            progress.project(done, proj_name);
             let proj_path = project.get("path").and_then(|v| v.as_str()).unwrap_or("");
             let branch = project
                 .get("branch")
@@ -2092,17 +2063,18 @@ impl McpServer {
 
             restored.push(proj_name.to_string());
         }
+        progress.finish();
 
-        Ok(serde_json::to_string_pretty(&serde_json::json!({
+        ToolOutput::json(&serde_json::json!({
             "status": if failed.is_empty() { "success" } else { "partial" },
             "restored": restored,
             "failed": failed,
             "restored_count": restored.len(),
             "failed_count": failed.len()
-        }))?)
+        }))
     }
 
-    fn tool_batch_execute(&self, args: &serde_json::Value) -> Result<String> {
+    fn tool_batch_execute(&self, args: &serde_json::Value) -> Result<ToolOutput> {
         let meta_dir = self
             .meta_dir
             .as_ref()
@@ -2144,55 +2116,55 @@ impl McpServer {
             None
         };
 
-        let mut results = Vec::new();
-        let mut has_failure = false;
+        // Atomic runs go one project at a time so they can stop at the first failure
+        let concurrency = if atomic { 1 } else { self.concurrency(args) };
+        let failed = AtomicBool::new(false);
+
+        let results = self.for_each_project(&filtered, concurrency, |project| {
+            if atomic && failed.load(Ordering::SeqCst) {
+                return None;
+            }
 
-        for project in &filtered {
             let project_path = meta_dir.join(&project.path);
             if !project_path.exists() {
-                results.push(serde_json::json!({
+                failed.store(true, Ordering::SeqCst);
+                return Some(serde_json::json!({
                     "project": project.name,
                     "success": false,
                     "error": "Path does not exist"
                 }));
-                has_failure = true;
-                continue;
             }
 
-            let output = Command::new("sh")
-                .arg("-c")
-                .arg(command)
-                .current_dir(&project_path)
-                .output();
+            let output = self.run_command(
+                Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .current_dir(&project_path),
+            );
 
-            match output {
+            Some(match output {
                 Ok(out) => {
                     let success = out.status.success();
                     if !success {
-                        has_failure = true;
+                        failed.store(true, Ordering::SeqCst);
                     }
-                    results.push(serde_json::json!({
+                    serde_json::json!({
                         "project": project.name,
                         "success": success,
                         "stdout": String::from_utf8_lossy(&out.stdout).to_string(),
                         "stderr": String::from_utf8_lossy(&out.stderr).to_string()
-                    }));
+                    })
                 }
                 Err(e) => {
-                    has_failure = true;
-                    results.push(serde_json::json!({
-                        "project": project.name,
-                        "success": false,
-                        "error": e.to_string()
-                    }));
+                    failed.store(true, Ordering::SeqCst);
+                    let mut result = failed_project(&project.name, &e);
+                    result["success"] = false.into();
+                    result
                 }
-            }
-
-            // If atomic and failure, stop and rollback
-            if atomic && has_failure {
-                break;
-            }
-        }
+            })
+        });
+        let results: Vec<serde_json::Value> = results.into_iter().flatten().collect();
+        let has_failure = failed.into_inner();
 
         // Rollback if atomic and failure
         let mut rollback_result = None;
@@ -2202,24 +2174,38 @@ impl McpServer {
                     "name": snapshot_name,
                     "force": true
                 });
-                rollback_result = Some(self.tool_snapshot_restore(&restore_args)?);
+                rollback_result = Some(self.tool_snapshot_restore(&restore_args)?.into_value());
             }
         }
 
-        Ok(serde_json::to_string_pretty(&serde_json::json!({
+        ToolOutput::json(&serde_json::json!({
             "command": command,
             "results": results,
             "has_failure": has_failure,
             "rolled_back": rollback_result.is_some(),
             "rollback_result": rollback_result
-        }))?)
+        }))
+    }
+
+    /// Run a git `command` in every project the call selects.
+    fn git_in_projects(&self, args: &serde_json::Value, command: &[&str]) -> Result<ToolOutput> {
+        let meta_dir = self
+            .meta_dir
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No meta repository found"))?;
+
+        let projects = self.load_projects_with_root(meta_dir)?;
+        let selected = selected_projects(&projects, args)?;
+        let results = self.for_each_project(&selected, self.concurrency(args), |project| {
+            self.run_in_project(meta_dir, project, command)
+        });
+
+        ToolOutput::json(&results)
     }
 
     fn git_output(&self, path: &std::path::Path, args: &[&str]) -> Result<String> {
-        let output = Command::new("git")
-            .args(args)
-            .current_dir(path)
-            .output()
+        let output = self
+            .run_command(Command::new("git").args(args).current_dir(path))
             .with_context(|| format!("Failed to run git {args:?}"))?;
 
         if output.status.success() {
@@ -2230,10 +2216,8 @@ impl McpServer {
     }
 
     fn git_command(&self, path: &std::path::Path, args: &[&str]) -> Result<()> {
-        let output = Command::new("git")
-            .args(args)
-            .current_dir(path)
-            .output()
+        let output = self
+            .run_command(Command::new("git").args(args).current_dir(path))
             .with_context(|| format!("Failed to run git {args:?}"))?;
 
         if output.status.success() {
@@ -2268,27 +2252,309 @@ impl McpServer {
         let (projects, _ignore) = config::parse_meta_config(&config_path)?;
         Ok(projects)
     }
+
+    /// The projects tools loop over the way `meta` does: led by the workspace's
+    /// own repository, named `.` as in `meta_git_multi_commit`, when it is one.
+    fn load_projects_with_root(&self, meta_dir: &std::path::Path) -> Result<Vec<ProjectInfo>> {
+        let mut projects = self.load_projects(meta_dir)?;
+        if meta_dir.join(".git").exists() {
+            projects.insert(
+                0,
+                ProjectInfo {
+                    name: ".".to_string(),
+                    path: ".".to_string(),
+                    repo: None,
+                    tags: Vec::new(),
+                },
+            );
+        }
+        Ok(projects)
+    }
+
+    /// Locate the config file that lives directly in `meta_dir`.
+    fn config_file(meta_dir: &std::path::Path) -> Option<PathBuf> {
+        [".meta", ".meta.yaml", ".meta.yml"]
+            .iter()
+            .map(|name| meta_dir.join(name))
+            .find(|path| path.exists())
+    }
+
+    /// `WorkspaceState` of every project that exists on disk, looking at up to
+    /// `concurrency` projects at once. Projects whose state cannot be read are
+    /// listed under `failed`.
+    fn workspace_state(
+        &self,
+        meta_dir: &std::path::Path,
+        concurrency: usize,
+    ) -> Result<serde_json::Value> {
+        let projects = self.load_projects(meta_dir)?;
+        let projects: Vec<&ProjectInfo> = projects.iter().collect();
+
+        let results = self.for_each_project(&projects, concurrency, |project| {
+            let project_path = meta_dir.join(&project.path);
+            if !project_path.exists() {
+                self.log_skipped(&project.name, "directory does not exist");
+                return None;
+            }
+            Some(
+                RepoState::collect(&project.name, &project_path, &project.tags)
+                    .map_err(|e| failed_project(&project.name, &anyhow::anyhow!(e))),
+            )
+        });
+
+        let mut repo_states = Vec::new();
+        let mut failed = Vec::new();
+        for result in results.into_iter().flatten() {
+            match result {
+                Ok(state) => repo_states.push(state),
+                Err(failure) => failed.push(failure),
+            }
+        }
+
+        let mut workspace_state = serde_json::to_value(WorkspaceState::from_repos(&repo_states))?;
+        if !failed.is_empty() {
+            workspace_state["failed"] = failed.into();
+        }
+        Ok(workspace_state)
+    }
+}
+
+/// Fewest requests a session handles at once, so a long build on a small
+/// machine does not hold up everything else.
+const MIN_REQUEST_WORKERS: usize = 4;
+
+/// How many requests a session handles at once: as many as projects are
+/// worked on at once, but at least `MIN_REQUEST_WORKERS`.
+fn request_workers() -> usize {
+    executor::default_concurrency().max(MIN_REQUEST_WORKERS)
+}
+
+/// Requests waiting for a worker, and how many workers are taking them. Both
+/// sit behind one lock so a request never waits without a worker to take it.
+#[derive(Default)]
+struct RequestQueue {
+    pending: VecDeque<(McpServer, JsonRpcRequest)>,
+    workers: usize,
+}
+
+impl RequestQueue {
+    /// Queue `request` and start a worker for it, unless `max_workers` are
+    /// already running. Returns the worker, if one was started.
+    fn push(
+        queue: &Arc<Mutex<Self>>,
+        request: (McpServer, JsonRpcRequest),
+        max_workers: usize,
+    ) -> Result<Option<JoinHandle<()>>> {
+        let mut state = queue
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Request queue lock poisoned"))?;
+        state.pending.push_back(request);
+        if state.workers >= max_workers {
+            return Ok(None);
+        }
+        state.workers += 1;
+
+        let queue = queue.clone();
+        Ok(Some(std::thread::spawn(move || loop {
+            let next = match queue.lock() {
+                Ok(mut state) => {
+                    let next = state.pending.pop_front();
+                    if next.is_none() {
+                        state.workers -= 1;
+                    }
+                    next
+                }
+                Err(_) => None,
+            };
+            match next {
+                Some((server, request)) => server.finish_request(&request),
+                None => return,
+            }
+        })))
+    }
+}
+
+/// Find the .meta config in the current directory or its parents.
+fn find_meta_dir() -> Option<PathBuf> {
+    std::env::current_dir()
+        .ok()
+        .and_then(|dir| find_meta_dir_from(&dir))
+}
+
+/// Find the .meta config in `dir` or its parents.
+fn find_meta_dir_from(dir: &std::path::Path) -> Option<PathBuf> {
+    config::find_meta_config(dir, None)
+        .map(|(config_path, _)| config_path.parent().unwrap().to_path_buf())
+}
+
+/// Command-line options. Without any, the server speaks MCP over stdio.
+#[derive(Debug, Default, PartialEq)]
+struct Options {
+    /// Serve streamable HTTP on this address instead of stdio.
+    http: Option<String>,
+    /// Serve clients connecting to this Unix socket instead of stdio.
+    socket: Option<PathBuf>,
+    /// Workspaces to serve, given with `--workspace`. Defaults to the one
+    /// containing the current directory.
+    workspaces: Vec<PathBuf>,
+    /// Offer only read-only tools (`--read-only`).
+    read_only: bool,
+    /// Let `--http` listen on addresses other machines can reach
+    /// (`--allow-remote`).
+    allow_remote: bool,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut options = Self::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--http" => {
+                    options.http = Some(
+                        args.next()
+                            .ok_or_else(|| anyhow::anyhow!("--http requires an address"))?,
+                    );
+                }
+                "--socket" => {
+                    options.socket = Some(
+                        args.next()
+                            .ok_or_else(|| anyhow::anyhow!("--socket requires a path"))?
+                            .into(),
+                    );
+                }
+                "--workspace" => {
+                    options.workspaces.push(
+                        args.next()
+                            .ok_or_else(|| anyhow::anyhow!("--workspace requires a directory"))?
+                            .into(),
+                    );
+                }
+                "--read-only" => options.read_only = true,
+                "--allow-remote" => options.allow_remote = true,
+                _ => anyhow::bail!(
+                    "Unknown argument: {arg}\nUsage: meta-mcp [--http ADDR [--allow-remote] | --socket PATH] [--workspace DIR]... [--read-only]"
+                ),
+            }
+        }
+        if options.http.is_some() && options.socket.is_some() {
+            anyhow::bail!("--http and --socket cannot be used together");
+        }
+        if options.allow_remote && options.http.is_none() {
+            anyhow::bail!("--allow-remote only applies to --http");
+        }
+        Ok(options)
+    }
 }
 
 fn main() -> Result<()> {
-    let mut server = McpServer::new();
-    server.run()
+    let options = Options::parse(std::env::args().skip(1))?;
+    let workspaces = workspaces::configured_workspaces(&options.workspaces)?;
+
+    if let Some(addr) = options.http {
+        return http::serve(&addr, workspaces, options.read_only, options.allow_remote);
+    }
+    if let Some(path) = options.socket {
+        #[cfg(unix)]
+        return socket::serve(&path, workspaces, options.read_only);
+        #[cfg(not(unix))]
+        anyhow::bail!("--socket is only supported on Unix ({})", path.display());
+    }
+    McpServer {
+        read_only: options.read_only,
+        ..McpServer::with_workspaces(workspaces, Outgoing::new(Box::new(std::io::stdout())))
+    }
+    .run()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    impl McpServer {
+        /// Handle one raw message synchronously, as `dispatch` does on a worker thread.
+        fn handle_message(&self, line: &str) -> Option<JsonRpcResponse> {
+            match self.parse_message(line) {
+                Ok(Incoming::Request(request)) => self.handle_request(&request),
+                Ok(Incoming::Response(response)) => {
+                    self.handle_client_response(response);
+                    None
+                }
+                Err(response) => Some(*response),
+            }
+        }
+    }
+
+    /// Writer that keeps everything written so tests can inspect notifications.
+    #[derive(Clone, Default)]
+    pub(crate) struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        pub(crate) fn messages(&self) -> Vec<serde_json::Value> {
+            let bytes = self.0.lock().unwrap().clone();
+            String::from_utf8(bytes)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+    }
+
     /// Create a server with a known meta_dir for tests that need one.
     fn server_with_meta_dir() -> (McpServer, tempfile::TempDir) {
         let tmp = tempfile::tempdir().unwrap();
         std::fs::write(tmp.path().join(".meta"), "{}").unwrap();
-        let server = McpServer {
-            meta_dir: Some(tmp.path().to_path_buf()),
-        };
+        let server = McpServer::with_meta_dir(
+            Some(tmp.path().to_path_buf()),
+            Outgoing::new(Box::new(std::io::sink())),
+        );
         (server, tmp)
     }
 
+    #[test]
+    fn test_options_parse() {
+        let parse = |args: &[&str]| Options::parse(args.iter().map(|a| a.to_string()));
+
+        assert_eq!(parse(&[]).unwrap(), Options::default());
+        assert_eq!(
+            parse(&["--http", "127.0.0.1:8080"])
+                .unwrap()
+                .http
+                .as_deref(),
+            Some("127.0.0.1:8080")
+        );
+        assert_eq!(
+            parse(&["--socket", "/tmp/meta.sock"]).unwrap().socket,
+            Some(PathBuf::from("/tmp/meta.sock"))
+        );
+        assert!(parse(&["--http"]).is_err());
+        assert!(parse(&["--http", "127.0.0.1:8080", "--socket", "/tmp/meta.sock"]).is_err());
+        assert_eq!(
+            parse(&["--workspace", "/work/a", "--workspace", "/work/b"])
+                .unwrap()
+                .workspaces,
+            [PathBuf::from("/work/a"), PathBuf::from("/work/b")]
+        );
+        assert!(parse(&["--workspace"]).is_err());
+        assert!(parse(&["--read-only"]).unwrap().read_only);
+        assert!(
+            parse(&["--http", "0.0.0.0:8080", "--allow-remote"])
+                .unwrap()
+                .allow_remote
+        );
+        assert!(parse(&["--allow-remote"]).is_err());
+        assert!(parse(&["--bogus"]).is_err());
+    }
+
     #[test]
     fn test_server_creation() {
         let server = McpServer::new();
@@ -2300,14 +2566,71 @@ mod tests {
     #[test]
     fn test_initialize_response() {
         let server = McpServer::new();
-        let result = server.handle_initialize().unwrap();
+        let result = server.handle_initialize(&serde_json::json!({})).unwrap();
 
         let result_obj = result.as_object().unwrap();
-        assert_eq!(result_obj.get("protocolVersion").unwrap(), PROTOCOL_VERSION);
+        assert_eq!(
+            result_obj.get("protocolVersion").unwrap(),
+            DEFAULT_PROTOCOL_VERSION
+        );
         assert!(result_obj.get("capabilities").is_some());
         assert!(result_obj.get("serverInfo").is_some());
     }
 
+    #[test]
+    fn test_initialize_negotiates_protocol_version() {
+        for version in SUPPORTED_PROTOCOL_VERSIONS {
+            let server = McpServer::new();
+            let result = server
+                .handle_initialize(&serde_json::json!({ "protocolVersion": version }))
+                .unwrap();
+            assert_eq!(result["protocolVersion"], *version);
+            assert_eq!(server.protocol_version(), *version);
+            assert_eq!(
+                result["capabilities"].get("completions").is_some(),
+                *version >= COMPLETIONS_VERSION
+            );
+        }
+
+        // Unknown revisions get our newest one back
+        let server = McpServer::new();
+        let result = server
+            .handle_initialize(&serde_json::json!({ "protocolVersion": "2099-01-01" }))
+            .unwrap();
+        assert_eq!(result["protocolVersion"], SUPPORTED_PROTOCOL_VERSIONS[0]);
+    }
+
+    #[test]
+    fn test_strip_unsupported_fields() {
+        let server = McpServer::new();
+        let tool = serde_json::json!({
+            "name": "meta_git_status",
+            "annotations": {"readOnlyHint": true},
+            "outputSchema": {"type": "object"}
+        });
+
+        let mut old_client = tool.clone();
+        server.strip_unsupported_fields(&mut old_client);
+        assert!(old_client.get("annotations").is_none());
+        assert!(old_client.get("outputSchema").is_none());
+
+        server
+            .handle_initialize(&serde_json::json!({ "protocolVersion": "2025-03-26" }))
+            .unwrap();
+        let mut mid_client = tool.clone();
+        server.strip_unsupported_fields(&mut mid_client);
+        assert!(mid_client.get("annotations").is_some());
+        assert!(mid_client.get("outputSchema").is_none());
+
+        server
+            .handle_initialize(&serde_json::json!({ "protocolVersion": "2025-06-18" }))
+            .unwrap();
+        let mut new_client = tool;
+        server.strip_unsupported_fields(&mut new_client);
+        assert!(new_client.get("annotations").is_some());
+        assert!(new_client.get("outputSchema").is_some());
+    }
+
     #[test]
     fn test_list_tools_response() {
         let server = McpServer::new();
@@ -2325,6 +2648,7 @@ mod tests {
 
         // Core tools
         assert!(tool_names.contains(&"meta_list_projects"));
+        assert!(tool_names.contains(&"meta_list_workspaces"));
         assert!(tool_names.contains(&"meta_exec"));
         assert!(tool_names.contains(&"meta_get_config"));
         assert!(tool_names.contains(&"meta_get_project_path"));
@@ -2362,8 +2686,312 @@ mod tests {
         assert!(tool_names.contains(&"meta_snapshot_restore"));
         assert!(tool_names.contains(&"meta_batch_execute"));
 
-        // Verify total count (4 core + 10 git + 4 build + 3 discovery + 8 AI = 29)
-        assert_eq!(tool_names.len(), 29);
+        // Verify total count (5 core + 10 git + 4 build + 3 discovery + 8 AI = 30)
+        assert_eq!(tool_names.len(), 30);
+
+        // Every tool but meta_list_workspaces can pick a workspace, and every
+        // tool can limit how long its processes run
+        for tool in tools {
+            let properties = &tool["inputSchema"]["properties"];
+            let has_workspace = properties.get("workspace").is_some();
+            assert_eq!(has_workspace, tool["name"] != "meta_list_workspaces");
+            assert!(properties.get("timeout_secs").is_some());
+        }
+    }
+
+    #[test]
+    fn test_output_schemas_are_objects() {
+        let server = McpServer::new();
+        server
+            .handle_initialize(&serde_json::json!({ "protocolVersion": "2025-06-18" }))
+            .unwrap();
+        let result = server.handle_list_tools().unwrap();
+
+        let tools = result["tools"].as_array().unwrap();
+        let with_schema: Vec<&serde_json::Value> =
+            tools.iter().filter_map(|t| t.get("outputSchema")).collect();
+        assert!(!with_schema.is_empty());
+        for schema in with_schema {
+            assert_eq!(schema["type"], "object");
+        }
+    }
+
+    #[test]
+    fn test_call_tool_structured_content() {
+        let (server, _tmp) = server_with_meta_dir();
+        let params = serde_json::json!({ "name": "meta_snapshot_list", "arguments": {} });
+
+        // Older revisions only get text
+        let result = server.handle_call_tool(&params).unwrap();
+        assert!(result.get("structuredContent").is_none());
+
+        server
+            .handle_initialize(&serde_json::json!({ "protocolVersion": "2025-06-18" }))
+            .unwrap();
+        let result = server.handle_call_tool(&params).unwrap();
+        assert_eq!(result["structuredContent"]["count"], 0);
+        let text: serde_json::Value =
+            serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(text, result["structuredContent"]);
+    }
+
+    #[test]
+    fn test_structured_content_wraps_arrays() {
+        let output = ToolOutput::Json(serde_json::json!([{ "project": "api" }]));
+        assert_eq!(
+            output.structured().unwrap(),
+            serde_json::json!({ "results": [{ "project": "api" }] })
+        );
+        assert!(ToolOutput::Text("plain".to_string()).structured().is_none());
+    }
+
+    #[test]
+    fn test_tool_annotations() {
+        let server = McpServer::new();
+        server
+            .handle_initialize(&serde_json::json!({ "protocolVersion": "2025-03-26" }))
+            .unwrap();
+        let result = server.handle_list_tools().unwrap();
+        let tools = result["tools"].as_array().unwrap();
+        let annotations = |name: &str| {
+            tools
+                .iter()
+                .find(|t| t["name"] == name)
+                .and_then(|t| t.get("annotations"))
+                .cloned()
+                .unwrap_or_else(|| panic!("{name} should have annotations"))
+        };
+
+        assert!(tools.iter().all(|t| t.get("annotations").is_some()));
+        assert_eq!(annotations("meta_git_status")["readOnlyHint"], true);
+        assert_eq!(annotations("meta_git_push")["openWorldHint"], true);
+        assert_eq!(annotations("meta_git_push")["readOnlyHint"], false);
+        assert_eq!(
+            annotations("meta_snapshot_restore")["destructiveHint"],
+            true
+        );
+        assert_eq!(annotations("meta_clean")["destructiveHint"], true);
+        assert_eq!(annotations("meta_batch_execute")["destructiveHint"], true);
+    }
+
+    #[test]
+    fn test_notifications_get_no_response() {
+        let server = McpServer::new();
+
+        for message in [
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            r#"{"jsonrpc":"2.0","method":"initialized"}"#,
+            r#"{"jsonrpc":"2.0","method":"notifications/unknown","params":{}}"#,
+            r#"{"jsonrpc":"2.0","method":"tools/list"}"#,
+        ] {
+            assert!(server.handle_message(message).is_none(), "{message}");
+        }
+
+        let response = server
+            .handle_message(r#"{"jsonrpc":"2.0","id":7,"method":"tools/list"}"#)
+            .unwrap();
+        assert_eq!(response.id, Some(serde_json::json!(7)));
+        assert!(response.result.is_some());
+    }
+
+    #[test]
+    fn test_cancelled_notification_marks_request() {
+        let server = McpServer::new();
+        let context = Arc::new(RequestContext::default());
+        server
+            .in_flight
+            .lock()
+            .unwrap()
+            .insert(serde_json::json!(42).to_string(), context.clone());
+
+        // A different id (string vs number) must not cancel it
+        server.handle_message(
+            r#"{"jsonrpc":"2.0","method":"notifications/cancelled","params":{"requestId":"42"}}"#,
+        );
+        assert!(!context.is_cancelled());
+
+        server.handle_message(
+            r#"{"jsonrpc":"2.0","method":"notifications/cancelled","params":{"requestId":42,"reason":"user"}}"#,
+        );
+        assert!(context.is_cancelled());
+    }
+
+    #[test]
+    fn test_requests_wait_for_a_free_worker() {
+        let buffer = SharedBuffer::default();
+        let server = McpServer::with_meta_dir(None, Outgoing::new(Box::new(buffer.clone())));
+        server.plugins.set(Vec::new());
+        let queue = Arc::new(Mutex::new(RequestQueue::default()));
+
+        let mut workers = Vec::new();
+        for id in 1..=3 {
+            let line = format!(r#"{{"jsonrpc":"2.0","id":{id},"method":"tools/list"}}"#);
+            let request = server.dispatch(&line).unwrap().unwrap();
+            workers.extend(RequestQueue::push(&queue, request, 1).unwrap());
+        }
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        // Every request was answered, by however few workers it took
+        let mut ids: Vec<u64> = buffer
+            .messages()
+            .iter()
+            .map(|m| m["id"].as_u64().unwrap())
+            .collect();
+        ids.sort();
+        assert_eq!(ids, [1, 2, 3]);
+        assert!(queue.lock().unwrap().pending.is_empty());
+        assert_eq!(queue.lock().unwrap().workers, 0);
+    }
+
+    #[test]
+    fn test_malformed_messages() {
+        let server = McpServer::new();
+
+        let response = server.handle_message("{not json").unwrap();
+        assert_eq!(response.error.unwrap().code, -32700);
+        assert!(response.id.is_none());
+        let json = serde_json::to_value(server.handle_message("{not json").unwrap()).unwrap();
+        assert!(json["id"].is_null());
+
+        let response = server
+            .handle_message(r#"{"jsonrpc":"2.0","id":3,"params":{}}"#)
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, -32600);
+        assert_eq!(response.id, Some(serde_json::json!(3)));
+
+        let response = server
+            .handle_message(r#"{"jsonrpc":"2.0","id":null,"method":"tools/list"}"#)
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, -32600);
+        assert!(response.id.is_none());
+
+        let response = server
+            .handle_message(r#"{"jsonrpc":"2.0","id":4,"method":"no/such/method"}"#)
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, -32601);
+
+        // A notification method sent with an id is still a request
+        let response = server
+            .handle_message(r#"{"jsonrpc":"2.0","id":5,"method":"notifications/foo"}"#)
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, -32601);
+        assert_eq!(response.id, Some(serde_json::json!(5)));
+
+        // Responses to requests we never sent are dropped, not answered
+        assert!(server
+            .handle_message(r#"{"jsonrpc":"2.0","id":"meta-mcp-99","result":{}}"#)
+            .is_none());
+    }
+
+    #[test]
+    fn test_invalid_tool_arguments() {
+        let server = McpServer::new();
+
+        let response = server
+            .handle_message(
+                r#"{"jsonrpc":"2.0","id":5,"method":"tools/call","params":{"name":"meta_get_file_tree","arguments":{"depth":"5","dpeth":3}}}"#,
+            )
+            .unwrap();
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32602);
+        assert!(error
+            .message
+            .contains("'depth' must be integer, got string"));
+        assert_eq!(
+            error.data.unwrap()["violations"],
+            serde_json::json!([
+                "'depth' must be integer, got string",
+                "unknown argument 'dpeth'"
+            ])
+        );
+
+        // The workspace argument is accepted, but must name a known workspace
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join(".meta"), r#"{"projects": {}}"#).unwrap();
+        let server = McpServer::with_meta_dir(
+            Some(tmp.path().to_path_buf()),
+            Outgoing::new(Box::new(std::io::sink())),
+        );
+        let call = |workspace: &str| {
+            let message = serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 6,
+                "method": "tools/call",
+                "params": {"name": "meta_list_projects", "arguments": {"workspace": workspace}}
+            });
+            server.handle_message(&message.to_string()).unwrap()
+        };
+        assert!(call(&tmp.path().display().to_string()).error.is_none());
+        assert_eq!(call("nope").error.unwrap().code, -32602);
+    }
+
+    #[test]
+    fn test_read_only_mode() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join(".meta"), r#"{"projects": {}}"#).unwrap();
+        let server = McpServer {
+            read_only: true,
+            ..McpServer::with_meta_dir(
+                Some(tmp.path().to_path_buf()),
+                Outgoing::new(Box::new(std::io::sink())),
+            )
+        };
+        server.plugins.set(Vec::new());
+
+        let result = server.handle_list_tools().unwrap();
+        let names: Vec<&str> = result["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        for name in [
+            "meta_git_status",
+            "meta_git_diff",
+            "meta_git_branch",
+            "meta_search_code",
+            "meta_get_file_tree",
+            "meta_query_repos",
+            "meta_analyze_impact",
+            "meta_execution_order",
+            "meta_snapshot_list",
+        ] {
+            assert!(names.contains(&name), "{name} should be listed");
+        }
+        for name in [
+            "meta_git_push",
+            "meta_clean",
+            "meta_snapshot_restore",
+            "meta_batch_execute",
+            "meta_exec",
+        ] {
+            assert!(!names.contains(&name), "{name} should be hidden");
+        }
+
+        let response = server
+            .handle_message(
+                r#"{"jsonrpc":"2.0","id":7,"method":"tools/call","params":{"name":"meta_git_push","arguments":{}}}"#,
+            )
+            .unwrap();
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32602);
+        assert_eq!(
+            error.message,
+            "Tool 'meta_git_push' is not available in read-only mode"
+        );
+
+        // The workspace settings can turn it on too
+        std::fs::write(tmp.path().join(".meta-mcp.yaml"), "read_only: true\n").unwrap();
+        let server = McpServer {
+            read_only: false,
+            ..server
+        };
+        assert!(server
+            .call_tool("meta_clean", &serde_json::json!({}))
+            .unwrap_err()
+            .is::<RpcError>());
     }
 
     #[test]
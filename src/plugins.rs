@@ -0,0 +1,739 @@
+//! Installed meta plugins as first-class tools.
+//!
+//! `meta --json plugins list` describes each plugin and, when the plugin
+//! declares them, its commands and their arguments. Every declared command
+//! becomes a tool named `meta_plugin_<plugin>_<command>` with an input schema
+//! built from those arguments, and a call is turned back into a `meta` command
+//! line. A plugin that declares no commands gets one tool taking raw `args`.
+//!
+//! The listing is read defensively, since its shape differs between meta
+//! versions: plugins may be a bare array or sit under `plugins`, and plugins,
+//! commands and arguments may each be given as a plain name or as an object.
+//! Entries that cannot be made sense of are skipped. So are commands with an
+//! argument named like one the server adds to every tool (`tag`, `workspace`,
+//! `timeout_secs`), since the two could not be told apart.
+
+use super::tools::ToolHandler;
+use super::workspace_tools::tool_name;
+use super::{McpServer, Tool, ToolAnnotations, ToolOutput};
+use crate::logging::LogLevel;
+use anyhow::{Context, Result};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How often the installed plugins are listed again.
+const PLUGIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long `meta plugins list` may take before there are no plugin tools.
+const PLUGIN_LIST_TIMEOUT: Duration = Duration::from_secs(30);
+
+const PLUGIN_PREFIX: &str = "meta_plugin_";
+
+/// Arguments every plugin tool takes from the server, which a plugin's own
+/// arguments must not be named like.
+const RESERVED_ARGUMENTS: &[&str] = &["tag", "workspace", "timeout_secs"];
+
+/// The installed plugins as last listed. The sessions of one HTTP or socket
+/// server share it, so plugins are listed once for all of them rather than
+/// once per session.
+#[derive(Clone, Default)]
+pub(crate) struct SharedPlugins {
+    plugins: Arc<Mutex<Option<Vec<Plugin>>>>,
+    /// When the plugins were last listed, or a refresh was claimed.
+    refreshed: Arc<Mutex<Option<Instant>>>,
+}
+
+impl SharedPlugins {
+    fn get(&self) -> Option<Vec<Plugin>> {
+        self.plugins.lock().ok().and_then(|plugins| plugins.clone())
+    }
+
+    pub(crate) fn set(&self, plugins: Vec<Plugin>) {
+        if let Ok(mut cached) = self.plugins.lock() {
+            *cached = Some(plugins);
+        }
+        if let Ok(mut refreshed) = self.refreshed.lock() {
+            *refreshed = Some(Instant::now());
+        }
+    }
+
+    /// Whether the plugins are due to be listed again. Only the first caller
+    /// to ask once they are due is told so.
+    fn claim_refresh(&self) -> bool {
+        let Ok(mut refreshed) = self.refreshed.lock() else {
+            return false;
+        };
+        if refreshed.is_some_and(|at| at.elapsed() < PLUGIN_REFRESH_INTERVAL) {
+            return false;
+        }
+        *refreshed = Some(Instant::now());
+        true
+    }
+}
+
+/// An installed meta plugin.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Plugin {
+    name: String,
+    description: Option<String>,
+    commands: Vec<PluginCommand>,
+}
+
+/// A command a plugin declares, e.g. `release prepare`.
+#[derive(Debug, Clone, PartialEq)]
+struct PluginCommand {
+    /// Words after the plugin name on the `meta` command line.
+    words: Vec<String>,
+    description: Option<String>,
+    arguments: Vec<PluginArgument>,
+    read_only: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PluginArgument {
+    name: String,
+    description: Option<String>,
+    /// JSON Schema type: string, boolean, integer, number or array.
+    value_type: &'static str,
+    required: bool,
+    /// The `--flag` passing it, or `None` for a positional argument.
+    flag: Option<String>,
+}
+
+/// A plugin command offered as a tool, or a whole plugin taking raw `args`.
+pub(crate) struct PluginTool {
+    definition: Tool,
+    plugin: String,
+    command: Option<PluginCommand>,
+}
+
+impl ToolHandler for PluginTool {
+    fn definition(&self) -> &Tool {
+        &self.definition
+    }
+
+    fn call(&self, server: &McpServer, args: &serde_json::Value) -> Result<ToolOutput> {
+        let command_line = match &self.command {
+            Some(command) => command.command_line(args)?,
+            // Passed as separate arguments, so nothing is split or interpreted by a shell
+            None => args
+                .get("args")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .map(plain_string)
+                .collect(),
+        };
+        server.run_plugin(&self.plugin, &command_line, args)
+    }
+}
+
+impl Plugin {
+    /// One tool per declared command, or a single raw tool. A command that
+    /// cannot be offered as a tool gives an error instead.
+    fn tools(&self) -> Vec<Result<PluginTool>> {
+        if self.commands.is_empty() {
+            let definition = Tool {
+                name: tool_name(PLUGIN_PREFIX, &self.name),
+                description: self
+                    .description
+                    .clone()
+                    .unwrap_or_else(|| format!("Run the '{}' meta plugin", self.name)),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "args": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Arguments passed to the plugin, e.g. [\"status\"]"
+                        },
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag"
+                        }
+                    }
+                }),
+                output_schema: None,
+                annotations: ToolAnnotations::destructive(false).open_world(),
+            };
+            return vec![Ok(PluginTool {
+                definition,
+                plugin: self.name.clone(),
+                command: None,
+            })];
+        }
+
+        self.commands
+            .iter()
+            .map(|command| {
+                let full_name = format!("{} {}", self.name, command.words.join(" "));
+                if let Some(argument) = command
+                    .arguments
+                    .iter()
+                    .find(|a| RESERVED_ARGUMENTS.contains(&a.name.as_str()))
+                {
+                    anyhow::bail!(
+                        "`meta {full_name}` is not offered: its argument '{}' clashes with the tool argument of that name",
+                        argument.name
+                    );
+                }
+                let definition = Tool {
+                    name: tool_name(PLUGIN_PREFIX, &full_name),
+                    description: command
+                        .description
+                        .clone()
+                        .unwrap_or_else(|| format!("Run `meta {full_name}`")),
+                    input_schema: command.input_schema(),
+                    output_schema: None,
+                    annotations: if command.read_only {
+                        ToolAnnotations::read_only()
+                    } else {
+                        ToolAnnotations::destructive(false).open_world()
+                    },
+                };
+                Ok(PluginTool {
+                    definition,
+                    plugin: self.name.clone(),
+                    command: Some(command.clone()),
+                })
+            })
+            .collect()
+    }
+}
+
+impl PluginCommand {
+    fn input_schema(&self) -> serde_json::Value {
+        let mut properties = serde_json::Map::new();
+        for argument in &self.arguments {
+            let mut property = serde_json::json!({ "type": argument.value_type });
+            if argument.value_type == "array" {
+                property["items"] = serde_json::json!({ "type": "string" });
+            }
+            if let Some(description) = &argument.description {
+                property["description"] = description.clone().into();
+            }
+            properties.insert(argument.name.clone(), property);
+        }
+        properties.insert(
+            "tag".to_string(),
+            serde_json::json!({
+                "type": "string",
+                "description": "Filter projects by tag"
+            }),
+        );
+
+        let required: Vec<&str> = self
+            .arguments
+            .iter()
+            .filter(|a| a.required)
+            .map(|a| a.name.as_str())
+            .collect();
+        serde_json::json!({
+            "type": "object",
+            "properties": properties,
+            "required": required
+        })
+    }
+
+    /// The arguments after `meta <plugin> <command>` for a call with `args`:
+    /// flags first, then positionals in declared order. Values that look like
+    /// options are refused, since `meta` or the plugin would parse them as one
+    /// rather than as the value of a flag or a positional.
+    fn command_line(&self, args: &serde_json::Value) -> Result<Vec<String>> {
+        let mut flags = Vec::new();
+        let mut positionals = Vec::new();
+        for argument in &self.arguments {
+            let value = match args.get(&argument.name) {
+                Some(value) if !value.is_null() => value,
+                _ if argument.required => {
+                    anyhow::bail!("Missing '{}' argument", argument.name)
+                }
+                _ => continue,
+            };
+
+            let values: Vec<String> = match value {
+                serde_json::Value::Bool(set) => {
+                    if let (true, Some(flag)) = (*set, &argument.flag) {
+                        flags.push(flag.clone());
+                    }
+                    continue;
+                }
+                serde_json::Value::Array(items) => items.iter().map(plain_string).collect(),
+                value => vec![plain_string(value)],
+            };
+            for value in values {
+                if value.starts_with('-') {
+                    anyhow::bail!(
+                        "Value '{value}' of '{}' must not start with '-'",
+                        argument.name
+                    );
+                }
+                match &argument.flag {
+                    Some(flag) => flags.extend([flag.clone(), value]),
+                    None => positionals.push(value),
+                }
+            }
+        }
+
+        Ok(self
+            .words
+            .iter()
+            .cloned()
+            .chain(flags)
+            .chain(positionals)
+            .collect())
+    }
+}
+
+impl McpServer {
+    /// A tool for each installed plugin command.
+    pub(crate) fn plugin_tools(&self) -> Vec<PluginTool> {
+        self.installed_plugins()
+            .iter()
+            .flat_map(Plugin::tools)
+            .filter_map(|tool| match tool {
+                Ok(tool) => Some(tool),
+                Err(e) => {
+                    self.log(LogLevel::Warning, e.to_string());
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn run_plugin(
+        &self,
+        plugin: &str,
+        command_line: &[String],
+        args: &serde_json::Value,
+    ) -> Result<ToolOutput> {
+        let meta_dir = self
+            .meta_dir
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No meta repository found"))?;
+
+        // Plugins are separate executables that the `meta` binary finds and
+        // launches; meta_cli has no library call for it
+        let mut cmd = Command::new("meta");
+        cmd.arg("--json");
+        if let Some(tag) = args.get("tag").and_then(|v| v.as_str()) {
+            cmd.arg("--tag").arg(tag);
+        }
+        cmd.arg(plugin).args(command_line);
+        cmd.current_dir(meta_dir);
+
+        let output = self
+            .run_command(&mut cmd)
+            .with_context(|| format!("Failed to run plugin '{plugin}'"))?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if output.status.success() {
+            Ok(ToolOutput::Text(stdout.to_string()))
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(anyhow::anyhow!(
+                "Plugin '{plugin}' failed:\n{stdout}\n{stderr}"
+            ))
+        }
+    }
+
+    /// The installed plugins, listed once and then kept until the watcher
+    /// refreshes them.
+    fn installed_plugins(&self) -> Vec<Plugin> {
+        if let Some(plugins) = self.plugins.get() {
+            return plugins;
+        }
+        self.refresh_plugins()
+    }
+
+    /// List the installed plugins again if no session sharing them has done
+    /// so within `PLUGIN_REFRESH_INTERVAL`.
+    pub(crate) fn refresh_stale_plugins(&self) {
+        if self.plugins.claim_refresh() {
+            self.refresh_plugins();
+        }
+    }
+
+    /// List the installed plugins again. Without a working `meta` binary there
+    /// are simply no plugin tools.
+    fn refresh_plugins(&self) -> Vec<Plugin> {
+        let plugins = match self.list_plugins() {
+            Ok(plugins) => plugins,
+            Err(e) => {
+                self.log(LogLevel::Debug, format!("No plugin tools: {e}"));
+                Vec::new()
+            }
+        };
+        self.plugins.set(plugins.clone());
+        plugins
+    }
+
+    fn list_plugins(&self) -> Result<Vec<Plugin>> {
+        Ok(parse_plugins(&self.plugin_listing()?))
+    }
+
+    /// What `meta --json plugins list` prints. Finding plugins is up to the
+    /// `meta` binary, so unlike the other tools this one needs it on `PATH`.
+    pub(crate) fn plugin_listing(&self) -> Result<serde_json::Value> {
+        let mut cmd = Command::new("meta");
+        cmd.arg("--json").arg("plugins").arg("list");
+        if let Some(meta_dir) = &self.meta_dir {
+            cmd.current_dir(meta_dir);
+        }
+        // Listing happens outside tool calls too, which have no limit of their own
+        let lister = McpServer {
+            timeout: Some(PLUGIN_LIST_TIMEOUT),
+            ..self.clone()
+        };
+        let output = lister
+            .run_command(&mut cmd)
+            .context("Failed to run meta plugins list")?;
+        if !output.status.success() {
+            anyhow::bail!("meta plugins list exited with {}", output.status);
+        }
+        serde_json::from_slice(&output.stdout).context("meta plugins list did not print JSON")
+    }
+}
+
+/// Read the plugins out of `meta --json plugins list`.
+fn parse_plugins(listed: &serde_json::Value) -> Vec<Plugin> {
+    entries(Some(listed), "plugins")
+        .iter()
+        .filter_map(|entry| {
+            let name = entry_name(entry)?;
+            let commands = entries(entry.get("commands"), "commands")
+                .iter()
+                .filter_map(|command| parse_command(&name, command))
+                .collect();
+            Some(Plugin {
+                description: entry_description(entry),
+                name,
+                commands,
+            })
+        })
+        .collect()
+}
+
+fn parse_command(plugin: &str, entry: &serde_json::Value) -> Option<PluginCommand> {
+    let name = entry_name(entry)?;
+    // Commands are listed either on their own or with the plugin name in front
+    let mut words: Vec<String> = name.split_whitespace().map(str::to_string).collect();
+    if words.len() > 1 && words[0] == plugin {
+        words.remove(0);
+    }
+
+    let arguments = entry
+        .get("arguments")
+        .or_else(|| entry.get("args"))
+        .and_then(|a| a.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(parse_argument)
+        .collect();
+    let read_only = ["readOnly", "read_only"]
+        .iter()
+        .any(|key| entry.get(key).and_then(|v| v.as_bool()) == Some(true));
+
+    Some(PluginCommand {
+        words,
+        description: entry_description(entry),
+        arguments,
+        read_only,
+    })
+}
+
+/// Arguments are positional unless they name a `long` flag, a `flag`, or are
+/// themselves written as `--name`. Booleans are always flags.
+fn parse_argument(entry: &serde_json::Value) -> Option<PluginArgument> {
+    let raw_name = entry_name(entry)?;
+    let name = raw_name.trim_start_matches('-').to_string();
+    if name.is_empty() {
+        return None;
+    }
+
+    let multiple = entry.get("multiple").and_then(|v| v.as_bool()) == Some(true);
+    let value_type = match entry.get("type").and_then(|v| v.as_str()) {
+        _ if multiple => "array",
+        Some("bool" | "boolean" | "flag") => "boolean",
+        Some("int" | "integer") => "integer",
+        Some("number" | "float") => "number",
+        Some("array" | "list") => "array",
+        _ => "string",
+    };
+
+    let flag = if let Some(long) = entry.get("long").and_then(|v| v.as_str()) {
+        Some(format!("--{}", long.trim_start_matches('-')))
+    } else if let Some(flag) = entry.get("flag").and_then(|v| v.as_str()) {
+        Some(flag.to_string())
+    } else if raw_name.starts_with('-') || value_type == "boolean" {
+        Some(format!("--{name}"))
+    } else {
+        None
+    };
+
+    Some(PluginArgument {
+        name,
+        description: entry_description(entry),
+        value_type,
+        required: entry.get("required").and_then(|v| v.as_bool()) == Some(true),
+        flag,
+    })
+}
+
+/// The array `value` holds, either directly or under `key`.
+fn entries(value: Option<&serde_json::Value>, key: &str) -> Vec<serde_json::Value> {
+    value
+        .and_then(|v| v.as_array().or_else(|| v.get(key)?.as_array()))
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// The name of an entry given as a string or as an object with a `name`.
+fn entry_name(entry: &serde_json::Value) -> Option<String> {
+    let name = entry
+        .as_str()
+        .or_else(|| entry.get("name")?.as_str())?
+        .trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+fn entry_description(entry: &serde_json::Value) -> Option<String> {
+    entry
+        .get("description")
+        .or_else(|| entry.get("about"))
+        .and_then(|d| d.as_str())
+        .map(str::to_string)
+}
+
+/// A JSON value as a command-line word, without quotes around strings.
+fn plain_string(value: &serde_json::Value) -> String {
+    match value.as_str() {
+        Some(value) => value.to_string(),
+        None => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release_plugin() -> Plugin {
+        parse_plugins(&serde_json::json!({
+            "plugins": [{
+                "name": "release",
+                "description": "Release tooling",
+                "commands": [
+                    {
+                        "name": "release prepare",
+                        "description": "Prepare a release",
+                        "arguments": [
+                            {"name": "version", "required": true, "description": "Version to release"},
+                            {"name": "dry-run", "type": "bool"},
+                            {"name": "channel", "long": "channel"},
+                            {"name": "--skip", "multiple": true}
+                        ]
+                    },
+                    {"name": "status", "readOnly": true}
+                ]
+            }]
+        }))
+        .remove(0)
+    }
+
+    #[test]
+    fn test_parse_plugins() {
+        let names = |listed: serde_json::Value| -> Vec<String> {
+            parse_plugins(&listed).into_iter().map(|p| p.name).collect()
+        };
+
+        assert_eq!(
+            names(serde_json::json!(["git", "release"])),
+            ["git", "release"]
+        );
+        assert_eq!(
+            names(
+                serde_json::json!({"plugins": [{"name": "deploy-check", "version": "1.0"}, {"path": "x"}, ""]})
+            ),
+            ["deploy-check"]
+        );
+        assert!(names(serde_json::json!("no plugins")).is_empty());
+        assert_eq!(
+            tool_name(PLUGIN_PREFIX, "deploy check"),
+            "meta_plugin_deploy_check"
+        );
+    }
+
+    #[test]
+    fn test_plugin_command_tools() {
+        let plugin = release_plugin();
+        let tools: Vec<PluginTool> = plugin.tools().into_iter().map(Result::unwrap).collect();
+        let names: Vec<&str> = tools.iter().map(|t| t.definition.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["meta_plugin_release_prepare", "meta_plugin_release_status"]
+        );
+
+        let prepare = &tools[0].definition;
+        let schema = &prepare.input_schema;
+        assert_eq!(schema["required"], serde_json::json!(["version"]));
+        assert_eq!(schema["properties"]["dry-run"]["type"], "boolean");
+        assert_eq!(schema["properties"]["skip"]["type"], "array");
+        assert_eq!(
+            schema["properties"]["version"]["description"],
+            "Version to release"
+        );
+        assert!(tools[1].definition.annotations.read_only_hint);
+        assert!(!prepare.annotations.read_only_hint);
+
+        // A plugin without declared commands takes raw arguments
+        let raw = parse_plugins(&serde_json::json!(["deploy-check"])).remove(0);
+        let tool = raw.tools().remove(0).unwrap();
+        assert_eq!(tool.definition.name, "meta_plugin_deploy-check");
+        assert!(tool.definition.input_schema["properties"]
+            .get("args")
+            .is_some());
+    }
+
+    #[test]
+    fn test_plugin_arguments_cannot_shadow_tool_arguments() {
+        for reserved in RESERVED_ARGUMENTS {
+            let plugin = parse_plugins(&serde_json::json!([{
+                "name": "deploy",
+                "commands": [
+                    {"name": "run", "arguments": [{"name": reserved}]},
+                    {"name": "status", "arguments": [{"name": "target"}]}
+                ]
+            }]))
+            .remove(0);
+            let tools = plugin.tools();
+
+            let err = tools[0].as_ref().err().unwrap();
+            assert!(err.to_string().contains(reserved), "{reserved}");
+            assert_eq!(
+                tools[1].as_ref().unwrap().definition.name,
+                "meta_plugin_deploy_status"
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_only_mode_hides_plugins_unless_listed() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join(".meta"), r#"{"projects": {}}"#).unwrap();
+        let registry = |settings: &str, read_only: bool| {
+            std::fs::write(tmp.path().join(".meta-mcp.yaml"), settings).unwrap();
+            let server = McpServer {
+                read_only,
+                ..McpServer::with_meta_dir(
+                    Some(tmp.path().to_path_buf()),
+                    crate::Outgoing::new(Box::new(std::io::sink())),
+                )
+            };
+            server.plugins.set(vec![release_plugin()]);
+            server.tool_registry()
+        };
+
+        // Declaring `readOnly` is not enough
+        let tools = registry("read_only: true\n", false);
+        assert!(tools.get("meta_plugin_release_status").is_none());
+        assert_eq!(
+            tools.withheld("meta_plugin_release_status"),
+            Some("is not available in read-only mode")
+        );
+
+        let listed = "read_only: true\nread_only_tools: [meta_plugin_release_status]\n";
+        let tools = registry(listed, false);
+        assert!(tools.get("meta_plugin_release_status").is_some());
+        assert!(tools.get("meta_plugin_release_prepare").is_none());
+
+        // The settings never loosen `--read-only`
+        let tools = registry(listed, true);
+        assert!(tools.get("meta_plugin_release_status").is_none());
+
+        // Nor bring back built-in tools
+        let tools = registry("read_only: true\nread_only_tools: [\"meta_*\"]\n", false);
+        assert!(tools.get("meta_plugin_release_prepare").is_some());
+        for name in ["meta_git_push", "meta_clean", "meta_batch_execute"] {
+            assert_eq!(
+                tools.withheld(name),
+                Some("is not available in read-only mode"),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_sessions_share_plugin_listing() {
+        let shared = SharedPlugins::default();
+        let session = || McpServer {
+            plugins: shared.clone(),
+            ..McpServer::with_meta_dir(None, crate::Outgoing::new(Box::new(std::io::sink())))
+        };
+        let (first, second) = (session(), session());
+
+        first.plugins.set(vec![release_plugin()]);
+        assert_eq!(second.installed_plugins(), [release_plugin()]);
+
+        // Just listed, so nobody lists them again yet
+        assert!(!first.plugins.claim_refresh());
+        assert!(!second.plugins.claim_refresh());
+
+        // Once due, one session lists them for all
+        *shared.refreshed.lock().unwrap() = None;
+        assert!(second.plugins.claim_refresh());
+        assert!(!first.plugins.claim_refresh());
+    }
+
+    #[test]
+    fn test_plugin_command_line() {
+        let plugin = release_plugin();
+        let prepare = &plugin.commands[0];
+
+        let line = prepare
+            .command_line(&serde_json::json!({
+                "version": "1.2.0",
+                "dry-run": true,
+                "channel": "beta",
+                "skip": ["docs", "web app"]
+            }))
+            .unwrap();
+        assert_eq!(
+            line,
+            [
+                "prepare",
+                "--dry-run",
+                "--channel",
+                "beta",
+                "--skip",
+                "docs",
+                "--skip",
+                "web app",
+                "1.2.0"
+            ]
+        );
+
+        let line = prepare
+            .command_line(&serde_json::json!({"version": "1.2.0", "dry-run": false}))
+            .unwrap();
+        assert_eq!(line, ["prepare", "1.2.0"]);
+
+        let err = prepare.command_line(&serde_json::json!({})).unwrap_err();
+        assert!(err.to_string().contains("version"));
+
+        for version in ["--force", "--env=prod", "-f"] {
+            let err = prepare
+                .command_line(&serde_json::json!({"version": version}))
+                .unwrap_err();
+            assert!(err.to_string().contains("must not start with '-'"));
+        }
+
+        // Nor can a flag's value pass as another flag
+        for args in [
+            serde_json::json!({"version": "1.2.0", "channel": "--force"}),
+            serde_json::json!({"version": "1.2.0", "skip": ["docs", "-rf"]}),
+        ] {
+            let err = prepare.command_line(&args).unwrap_err();
+            assert!(err.to_string().contains("must not start with '-'"));
+        }
+    }
+}
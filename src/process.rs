@@ -0,0 +1,533 @@
+//! Spawning external commands on behalf of a request.
+//!
+//! `Command::output()` blocks until the child exits, which leaves no way to stop
+//! a runaway `cargo test`. `run_command` waits by polling instead, so the child
+//! and everything it started can be killed as soon as the client cancels the
+//! request that started it, or once it has run longer than the tool call
+//! allows. A call's limit is its `timeout_secs` argument, or else the limit
+//! the workspace configures for the tool, or else the tool's default from
+//! `DEFAULT_TIMEOUTS`.
+//!
+//! Only processes started here are covered. `meta_workspace_state`,
+//! `meta_query_repos` and the state resources read git state through
+//! `RepoState::collect`, which spawns git itself, so no limit applies to them
+//! and a hung git holds those calls until it exits.
+
+use super::logging::LogLevel;
+use super::server_config::ServerConfig;
+use super::McpServer;
+use anyhow::{Context, Result};
+use std::io::Read;
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How often a running child is checked for exit or cancellation.
+const POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// Limit for each process a tool spawns, unless listed in `DEFAULT_TIMEOUTS`.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Tools whose processes routinely run longer (builds, test suites) or should
+/// give up sooner (searches) than `DEFAULT_TIMEOUT`, in seconds.
+const DEFAULT_TIMEOUTS: &[(&str, u64)] = &[
+    ("meta_build", 30 * 60),
+    ("meta_run_tests", 30 * 60),
+    ("meta_batch_execute", 30 * 60),
+    ("meta_exec", 30 * 60),
+    ("meta_search_code", 60),
+    ("meta_git_diff", 60),
+    ("meta_git_branch", 60),
+];
+
+/// A process killed for running past the tool call's time limit.
+#[derive(Debug)]
+pub(crate) struct TimedOut {
+    command: String,
+    limit: Duration,
+}
+
+impl std::fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} timed out after {}s",
+            self.command,
+            self.limit.as_secs()
+        )
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+/// The time limit for each process of a call to `tool` with `args`.
+pub(crate) fn tool_timeout(
+    tool: &str,
+    args: &serde_json::Value,
+    config: &ServerConfig,
+) -> Duration {
+    if let Some(secs) = args
+        .get("timeout_secs")
+        .and_then(|v| v.as_u64())
+        .or_else(|| config.timeout_secs(tool))
+    {
+        return Duration::from_secs(secs.max(1));
+    }
+    DEFAULT_TIMEOUTS
+        .iter()
+        .find(|(name, _)| *name == tool)
+        .map_or(DEFAULT_TIMEOUT, |(_, secs)| Duration::from_secs(*secs))
+}
+
+/// Add the optional `timeout_secs` argument to a tool's input schema.
+pub(crate) fn add_timeout_argument(input_schema: &mut serde_json::Value) {
+    if let Some(properties) = input_schema
+        .get_mut("properties")
+        .and_then(|p| p.as_object_mut())
+    {
+        properties.insert(
+            "timeout_secs".to_string(),
+            serde_json::json!({
+                "type": "integer",
+                "minimum": 1,
+                "description": "Seconds each process the tool starts may run before it is killed (default depends on the tool)"
+            }),
+        );
+    }
+}
+
+/// The result for a project whose command could not run to completion.
+pub(crate) fn failed_project(project: &str, error: &anyhow::Error) -> serde_json::Value {
+    let mut result = serde_json::json!({
+        "project": project,
+        "error": error.to_string()
+    });
+    if error.is::<TimedOut>() {
+        result["timed_out"] = true.into();
+    }
+    result
+}
+
+impl McpServer {
+    /// Run `cmd` to completion and collect its output, like `Command::output()`,
+    /// but kill it if the current request is cancelled or it runs past this
+    /// handle's time limit. Processes the child leaves running in the
+    /// background may keep its output open; they are waited for only within
+    /// the same limit. Output beyond the handle's output limit is cut from the
+    /// front, keeping the end where failures are usually reported.
+    pub(crate) fn run_command(&self, cmd: &mut Command) -> Result<Output> {
+        if self.is_cancelled() {
+            anyhow::bail!("Request cancelled");
+        }
+
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        // Give the child its own process group so the whole tree (e.g. the
+        // test binaries under `sh -c "cargo test"`) can be killed at once
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(cmd, 0);
+
+        let description = describe(cmd);
+        self.log(LogLevel::Debug, format!("Running {description}"));
+        let started = Instant::now();
+
+        let mut child = cmd
+            .spawn()
+            .with_context(|| format!("Failed to spawn {:?}", cmd.get_program()))?;
+
+        // Drain both pipes on their own threads so a chatty child cannot fill
+        // a pipe buffer and block forever
+        let stdout = drain(child.stdout.take());
+        let stderr = drain(child.stderr.take());
+
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if self.is_cancelled() {
+                kill_tree(&mut child);
+                self.log(LogLevel::Debug, format!("Killed {description} on cancel"));
+                anyhow::bail!("Request cancelled");
+            }
+            if let Some(limit) = self.timeout.filter(|limit| started.elapsed() >= *limit) {
+                kill_tree(&mut child);
+                self.log(
+                    LogLevel::Warning,
+                    format!("Killed {description} on timeout"),
+                );
+                return Err(TimedOut {
+                    command: description,
+                    limit,
+                }
+                .into());
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        };
+        self.log(
+            LogLevel::Debug,
+            format!(
+                "Finished {description} in {}ms ({status})",
+                started.elapsed().as_millis()
+            ),
+        );
+
+        // Processes the child left running in the background (`npm start &`)
+        // keep the pipes open, but cannot hold the call past its limit
+        while !(stdout.is_finished() && stderr.is_finished()) {
+            let timed_out = self.timeout.is_some_and(|limit| started.elapsed() >= limit);
+            if self.is_cancelled() || timed_out {
+                self.log(
+                    LogLevel::Warning,
+                    format!("Stopped waiting for the output of {description}"),
+                );
+                break;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+
+        Ok(Output {
+            status,
+            stdout: self.limit_output(stdout.take()),
+            stderr: self.limit_output(stderr.take()),
+        })
+    }
+
+    fn limit_output(&self, output: Vec<u8>) -> Vec<u8> {
+        match self.output_limit {
+            Some(limit) if output.len() > limit => {
+                let cut = output.len() - limit;
+                let mut kept = format!("[{cut} bytes of output cut]\n").into_bytes();
+                kept.extend_from_slice(&output[cut..]);
+                kept
+            }
+            _ => output,
+        }
+    }
+
+    /// Whether the request this handle is serving has been cancelled.
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.request
+            .as_ref()
+            .map(|request| request.is_cancelled())
+            .unwrap_or(false)
+    }
+}
+
+/// Render a command line and its working directory for log messages.
+fn describe(cmd: &Command) -> String {
+    let mut line = cmd.get_program().to_string_lossy().to_string();
+    for arg in cmd.get_args() {
+        line.push(' ');
+        line.push_str(&arg.to_string_lossy());
+    }
+    match cmd.get_current_dir() {
+        Some(dir) => format!("`{line}` in {}", dir.display()),
+        None => format!("`{line}`"),
+    }
+}
+
+/// Kill `child` and all of its descendants, then reap it.
+fn kill_tree(child: &mut Child) {
+    #[cfg(unix)]
+    kill_group(child.id());
+
+    #[cfg(windows)]
+    let _ = Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &child.id().to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Kill the process group `run_command` started the child `pid` in.
+#[cfg(unix)]
+fn kill_group(pid: u32) {
+    let Ok(pgid) = libc::pid_t::try_from(pid) else {
+        return;
+    };
+    // SAFETY: kill(2) takes no pointers; a negative pid names the group, which
+    // is the child's own since it was spawned with `process_group(0)`
+    unsafe {
+        libc::kill(-pgid, libc::SIGKILL);
+    }
+}
+
+/// Output read from a child's pipe so far, by a thread reading until it closes.
+struct Drain {
+    output: Arc<Mutex<Vec<u8>>>,
+    reader: JoinHandle<()>,
+}
+
+impl Drain {
+    fn is_finished(&self) -> bool {
+        self.reader.is_finished()
+    }
+
+    /// The output read so far. Reading may still go on if the pipe is open.
+    fn take(&self) -> Vec<u8> {
+        self.output
+            .lock()
+            .map(|mut output| std::mem::take(&mut *output))
+            .unwrap_or_default()
+    }
+}
+
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> Drain {
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let buffer = output.clone();
+    let reader = std::thread::spawn(move || {
+        let mut pipe = match pipe {
+            Some(pipe) => pipe,
+            None => return,
+        };
+        let mut chunk = [0; 8192];
+        loop {
+            let read = match pipe.read(&mut chunk) {
+                Ok(0) => return,
+                Ok(read) => read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => return,
+            };
+            match buffer.lock() {
+                Ok(mut buffer) => buffer.extend_from_slice(&chunk[..read]),
+                Err(_) => return,
+            }
+        }
+    });
+    Drain { output, reader }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Outgoing, RequestContext};
+    use std::sync::Arc;
+
+    fn server_for_request() -> (McpServer, Arc<RequestContext>) {
+        let context = Arc::new(RequestContext::default());
+        let server = McpServer {
+            request: Some(context.clone()),
+            ..McpServer::with_meta_dir(None, Outgoing::new(Box::new(std::io::sink())))
+        };
+        (server, context)
+    }
+
+    #[test]
+    fn test_run_command_collects_output() {
+        let (server, _context) = server_for_request();
+        let output = server
+            .run_command(Command::new("git").arg("--version"))
+            .unwrap();
+
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout).contains("git version"));
+    }
+
+    #[test]
+    fn test_output_limit_keeps_the_end() {
+        let server = McpServer {
+            output_limit: Some(4),
+            ..server_for_request().0
+        };
+        let output = server
+            .run_command(Command::new("sh").args(["-c", "printf 'abcdefgh'"]))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "[4 bytes of output cut]\nefgh"
+        );
+    }
+
+    #[test]
+    fn test_run_command_refuses_cancelled_request() {
+        let (server, context) = server_for_request();
+        context.cancel();
+
+        let err = server
+            .run_command(Command::new("git").arg("--version"))
+            .unwrap_err();
+        assert!(err.to_string().contains("cancelled"));
+    }
+
+    #[test]
+    fn test_tool_timeout() {
+        let config = ServerConfig::default();
+        assert_eq!(
+            tool_timeout("meta_run_tests", &serde_json::json!({}), &config),
+            Duration::from_secs(30 * 60)
+        );
+        assert_eq!(
+            tool_timeout("meta_git_status", &serde_json::json!({}), &config),
+            DEFAULT_TIMEOUT
+        );
+        assert_eq!(
+            tool_timeout(
+                "meta_run_tests",
+                &serde_json::json!({"timeout_secs": 5}),
+                &config
+            ),
+            Duration::from_secs(5)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_timeout_kills_process_tree() {
+        let tmp = tempfile::tempdir().unwrap();
+        let marker = tmp.path().join("finished");
+        let server = McpServer {
+            timeout: Some(Duration::from_millis(100)),
+            ..server_for_request().0
+        };
+
+        let err = server
+            .run_command(
+                Command::new("sh")
+                    .args(["-c", "sleep 1; touch finished"])
+                    .current_dir(tmp.path()),
+            )
+            .unwrap_err();
+
+        assert!(err.is::<TimedOut>());
+        assert!(err.to_string().contains("timed out"));
+        assert_eq!(
+            failed_project("api", &err)["timed_out"],
+            serde_json::json!(true)
+        );
+
+        std::thread::sleep(Duration::from_millis(1500));
+        assert!(!marker.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_timed_out_project_is_reported() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            tmp.path().join(".meta"),
+            r#"{"projects": {"api": "x", "web": "y"}}"#,
+        )
+        .unwrap();
+        std::fs::create_dir_all(tmp.path().join("api")).unwrap();
+        std::fs::create_dir_all(tmp.path().join("web")).unwrap();
+        std::fs::write(tmp.path().join("api/slow"), "").unwrap();
+        let server = McpServer::with_meta_dir(
+            Some(tmp.path().to_path_buf()),
+            Outgoing::new(Box::new(std::io::sink())),
+        );
+
+        let output = server
+            .call_tool(
+                "meta_batch_execute",
+                &serde_json::json!({
+                    "command": "if [ -e slow ]; then sleep 5; fi",
+                    "timeout_secs": 1
+                }),
+            )
+            .unwrap()
+            .into_value();
+
+        let results = output["results"].as_array().unwrap();
+        assert_eq!(results[0]["project"], "api");
+        assert_eq!(results[0]["timed_out"], true);
+        assert_eq!(results[0]["success"], false);
+        assert_eq!(results[1]["success"], true);
+        assert!(results[1].get("timed_out").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_background_processes_do_not_hold_the_call() {
+        let server = McpServer {
+            timeout: Some(Duration::from_millis(200)),
+            ..server_for_request().0
+        };
+
+        // The child exits at once; its `sleep` keeps stdout open well past
+        // the limit, and the call returns the child's outcome anyway
+        let output = server
+            .run_command(Command::new("sh").args(["-c", "sleep 30 & echo started"]))
+            .unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "started\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_timed_out_diff_is_reported_per_project() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join(".meta"), r#"{"projects": {"api": "x"}}"#).unwrap();
+        let api = tmp.path().join("api");
+        std::fs::create_dir_all(&api).unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(args)
+                .current_dir(&api)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success());
+        };
+        git(&["init", "-q"]);
+        git(&["config", "user.name", "Test"]);
+        git(&["config", "user.email", "test@example.com"]);
+        std::fs::write(api.join("README.md"), "api").unwrap();
+        git(&["add", "README.md"]);
+        git(&["commit", "-q", "-m", "Add README"]);
+        std::fs::write(api.join("README.md"), "api v2").unwrap();
+
+        // An external diff tool that hangs
+        let slow_diff = tmp.path().join("slow-diff");
+        std::fs::write(&slow_diff, "#!/bin/sh\nsleep 5\n").unwrap();
+        std::fs::set_permissions(&slow_diff, std::fs::Permissions::from_mode(0o755)).unwrap();
+        git(&["config", "diff.external", slow_diff.to_str().unwrap()]);
+
+        let server = McpServer::with_meta_dir(
+            Some(tmp.path().to_path_buf()),
+            Outgoing::new(Box::new(std::io::sink())),
+        );
+        let output = server
+            .call_tool("meta_git_diff", &serde_json::json!({"timeout_secs": 1}))
+            .unwrap()
+            .into_value();
+
+        assert_eq!(output[0]["project"], "api");
+        assert_eq!(output[0]["timed_out"], true);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cancel_kills_process_tree() {
+        let tmp = tempfile::tempdir().unwrap();
+        let marker = tmp.path().join("finished");
+        let (server, context) = server_for_request();
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            context.cancel();
+        });
+
+        let err = server
+            .run_command(
+                Command::new("sh")
+                    .args(["-c", "sleep 1; touch finished"])
+                    .current_dir(tmp.path()),
+            )
+            .unwrap_err();
+        canceller.join().unwrap();
+
+        assert!(err.to_string().contains("cancelled"));
+
+        // The shell would have created the marker had it survived
+        std::thread::sleep(Duration::from_millis(1500));
+        assert!(!marker.exists());
+    }
+}
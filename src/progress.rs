@@ -0,0 +1,170 @@
+//! `notifications/progress` for tools that walk every project.
+//!
+//! When a request carries `_meta.progressToken`, the per-project loops report
+//! how many projects are done and which one is being worked on (or, when they
+//! run in parallel, which one just finished), so a slow repository is visible
+//! while it is still running.
+
+use super::McpServer;
+use std::sync::Mutex;
+
+/// First protocol revision that allows a `message` on progress notifications.
+const PROGRESS_MESSAGE_VERSION: &str = "2025-03-26";
+
+/// Reports progress through a fixed number of projects. Does nothing when the
+/// client did not ask for progress.
+pub(crate) struct Progress<'a> {
+    server: &'a McpServer,
+    token: Option<serde_json::Value>,
+    total: usize,
+    /// Progress value last sent. Sending under this lock keeps the values
+    /// increasing when several threads report.
+    sent: Mutex<Option<usize>>,
+}
+
+impl McpServer {
+    /// Start reporting progress over `total` projects.
+    ///
+    /// Only the first reporter of a request gets the token, so a tool that calls
+    /// another one (e.g. the rollback in `meta_batch_execute`) does not send
+    /// progress that goes backwards.
+    pub(crate) fn progress(&self, total: usize) -> Progress<'_> {
+        let token = self
+            .request
+            .as_ref()
+            .and_then(|request| request.progress_token.lock().ok()?.take());
+        Progress {
+            server: self,
+            token,
+            total,
+            sent: Mutex::new(None),
+        }
+    }
+}
+
+impl Progress<'_> {
+    /// Report that `done` projects have finished and `project` is starting.
+    pub(crate) fn project(&self, done: usize, project: &str) {
+        if let Ok(mut sent) = self.sent.lock() {
+            self.send(&mut sent, done, project.to_string());
+        }
+    }
+
+    /// Report that one more project, `project`, has finished.
+    pub(crate) fn project_finished(&self, project: &str) {
+        if let Ok(mut sent) = self.sent.lock() {
+            let done = sent.map_or(1, |n| n + 1);
+            self.send(&mut sent, done, project.to_string());
+        }
+    }
+
+    /// Report that every project has finished, unless that was already the
+    /// last report.
+    pub(crate) fn finish(&self) {
+        if let Ok(mut sent) = self.sent.lock() {
+            if *sent != Some(self.total) {
+                self.send(&mut sent, self.total, "Done".to_string());
+            }
+        }
+    }
+
+    fn send(&self, sent: &mut Option<usize>, progress: usize, message: String) {
+        let token = match self.token.as_ref() {
+            Some(token) => token,
+            None => return,
+        };
+        *sent = Some(progress);
+
+        let mut params = serde_json::json!({
+            "progressToken": token,
+            "progress": progress,
+            "total": self.total,
+        });
+        if self.server.protocol_version() >= PROGRESS_MESSAGE_VERSION {
+            params["message"] = serde_json::Value::String(message);
+        }
+
+        if let Err(e) = self
+            .server
+            .send_notification("notifications/progress", params)
+        {
+            eprintln!("Failed to send progress: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::SharedBuffer;
+    use crate::{Outgoing, RequestContext};
+    use std::sync::{Arc, Mutex};
+
+    fn server_with_token(buffer: &SharedBuffer) -> (McpServer, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            tmp.path().join(".meta"),
+            r#"{"projects": {"api": "x", "web": "y"}}"#,
+        )
+        .unwrap();
+        std::fs::create_dir_all(tmp.path().join("api")).unwrap();
+        std::fs::create_dir_all(tmp.path().join("web")).unwrap();
+
+        let server = McpServer {
+            request: Some(Arc::new(RequestContext {
+                progress_token: Mutex::new(Some(serde_json::json!("tok-1"))),
+                ..RequestContext::default()
+            })),
+            ..McpServer::with_meta_dir(
+                Some(tmp.path().to_path_buf()),
+                Outgoing::new(Box::new(buffer.clone())),
+            )
+        };
+        *server.protocol_version.lock().unwrap() = "2025-06-18";
+        (server, tmp)
+    }
+
+    #[test]
+    fn test_progress_reported_per_project() {
+        let buffer = SharedBuffer::default();
+        let (server, _tmp) = server_with_token(&buffer);
+        server
+            .tool_batch_execute(&serde_json::json!({"command": "true", "concurrency": 1}))
+            .unwrap();
+
+        let messages = buffer.messages();
+        let progress: Vec<(u64, &str)> = messages
+            .iter()
+            .map(|m| {
+                assert_eq!(m["method"], "notifications/progress");
+                assert_eq!(m["params"]["progressToken"], "tok-1");
+                assert_eq!(m["params"]["total"], 2);
+                (
+                    m["params"]["progress"].as_u64().unwrap(),
+                    m["params"]["message"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(progress, [(1, "api"), (2, "web")]);
+    }
+
+    #[test]
+    fn test_progress_token_used_once() {
+        let buffer = SharedBuffer::default();
+        let (server, _tmp) = server_with_token(&buffer);
+        server.progress(1).finish();
+        server.progress(1).finish();
+
+        assert_eq!(buffer.messages().len(), 1);
+    }
+
+    #[test]
+    fn test_no_progress_without_token() {
+        let buffer = SharedBuffer::default();
+        let server = McpServer::with_meta_dir(None, Outgoing::new(Box::new(buffer.clone())));
+        server.progress(3).project(0, "api");
+        server.progress(3).finish();
+
+        assert!(buffer.messages().is_empty());
+    }
+}
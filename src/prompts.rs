@@ -0,0 +1,283 @@
+//! MCP prompts: reusable multi-repo playbooks with live workspace data.
+//!
+//! Each prompt renders a single user message that embeds the current output of
+//! the relevant tools, so the assistant starts from real state rather than
+//! having to call the tools itself.
+
+use super::{McpServer, RpcError};
+use anyhow::Result;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+struct Prompt {
+    name: &'static str,
+    description: &'static str,
+    arguments: Vec<PromptArgument>,
+}
+
+#[derive(Debug, Serialize)]
+struct PromptArgument {
+    name: &'static str,
+    description: &'static str,
+    required: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ListPromptsResult {
+    prompts: Vec<Prompt>,
+}
+
+#[derive(Debug, Serialize)]
+struct PromptMessage {
+    role: &'static str,
+    content: PromptContent,
+}
+
+#[derive(Debug, Serialize)]
+struct PromptContent {
+    #[serde(rename = "type")]
+    content_type: &'static str,
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+struct GetPromptResult {
+    description: &'static str,
+    messages: Vec<PromptMessage>,
+}
+
+fn prompts() -> Vec<Prompt> {
+    vec![
+        Prompt {
+            name: "prepare_release",
+            description: "Prepare a coordinated release across the workspace's repositories",
+            arguments: vec![
+                PromptArgument {
+                    name: "version",
+                    description: "Version being released (optional)",
+                    required: false,
+                },
+                PromptArgument {
+                    name: "tag",
+                    description: "Only include projects with this tag (optional)",
+                    required: false,
+                },
+            ],
+        },
+        Prompt {
+            name: "review_changes",
+            description: "Review uncommitted changes across the workspace",
+            arguments: vec![
+                PromptArgument {
+                    name: "project",
+                    description: "Only review this project (optional)",
+                    required: false,
+                },
+                PromptArgument {
+                    name: "tag",
+                    description: "Only review projects with this tag (optional)",
+                    required: false,
+                },
+            ],
+        },
+        Prompt {
+            name: "analyze_change_impact",
+            description: "Summarize the impact of changing a project on the rest of the workspace",
+            arguments: vec![PromptArgument {
+                name: "project",
+                description: "Project that is going to change",
+                required: true,
+            }],
+        },
+    ]
+}
+
+impl McpServer {
+    pub(crate) fn handle_list_prompts(&self) -> Result<serde_json::Value> {
+        Ok(serde_json::to_value(ListPromptsResult {
+            prompts: prompts(),
+        })?)
+    }
+
+    pub(crate) fn handle_get_prompt(
+        &self,
+        params: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let name = params
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| RpcError::new(-32602, "Missing prompt name"))?;
+
+        let prompt = prompts()
+            .into_iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| RpcError::new(-32602, format!("Unknown prompt: {name}")))?;
+
+        let arguments = params
+            .get("arguments")
+            .cloned()
+            .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
+
+        for argument in prompt.arguments.iter().filter(|a| a.required) {
+            if arguments
+                .get(argument.name)
+                .and_then(|v| v.as_str())
+                .is_none()
+            {
+                return Err(RpcError::new(
+                    -32602,
+                    format!("Missing required argument '{}'", argument.name),
+                )
+                .into());
+            }
+        }
+
+        let text = match name {
+            "prepare_release" => self.prompt_prepare_release(&arguments)?,
+            "review_changes" => self.prompt_review_changes(&arguments)?,
+            "analyze_change_impact" => self.prompt_analyze_change_impact(&arguments)?,
+            _ => unreachable!("prompt list and dispatch are out of sync"),
+        };
+
+        let result = GetPromptResult {
+            description: prompt.description,
+            messages: vec![PromptMessage {
+                role: "user",
+                content: PromptContent {
+                    content_type: "text",
+                    text,
+                },
+            }],
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+
+    fn prompt_prepare_release(&self, args: &serde_json::Value) -> Result<String> {
+        let version = args
+            .get("version")
+            .and_then(|v| v.as_str())
+            .map(|v| format!(" version {v}"))
+            .unwrap_or_default();
+        let scope = serde_json::json!({ "tag": args.get("tag") });
+
+        let state = self.tool_workspace_state(&scope)?;
+        let branches = self.tool_git_branch(&scope)?;
+        let order = self.tool_execution_order(&scope)?;
+
+        Ok(format!(
+            "Prepare a cross-repo release{version} for this meta workspace.\n\n\
+             1. Flag any repository that is dirty, not on its release branch, or behind its upstream.\n\
+             2. Propose the order in which repositories should be released, respecting dependencies.\n\
+             3. List the version bumps and changelog entries each repository needs.\n\
+             4. Call out anything that blocks the release.\n\n\
+             ## Workspace state\n```json\n{state}\n```\n\n\
+             ## Branches\n```json\n{branches}\n```\n\n\
+             ## Dependency execution order\n```json\n{order}\n```\n"
+        ))
+    }
+
+    fn prompt_review_changes(&self, args: &serde_json::Value) -> Result<String> {
+        let scope = serde_json::json!({
+            "project": args.get("project"),
+            "tag": args.get("tag"),
+        });
+        let staged_scope = serde_json::json!({
+            "project": args.get("project"),
+            "tag": args.get("tag"),
+            "staged": true,
+        });
+
+        let unstaged = self.tool_git_diff(&scope)?;
+        let staged = self.tool_git_diff(&staged_scope)?;
+
+        Ok(format!(
+            "Review the uncommitted changes across this meta workspace.\n\n\
+             For each repository, summarize what changed, point out bugs, risky edits and \
+             missing tests, and note changes in one repository that require follow-up in another.\n\n\
+             ## Staged changes\n```json\n{staged}\n```\n\n\
+             ## Unstaged changes\n```json\n{unstaged}\n```\n"
+        ))
+    }
+
+    fn prompt_analyze_change_impact(&self, args: &serde_json::Value) -> Result<String> {
+        let project = args
+            .get("project")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+
+        let impact = self.tool_analyze_impact(args)?;
+        let state = self.tool_workspace_state(args)?;
+
+        Ok(format!(
+            "Summarize the impact of changing the '{project}' project.\n\n\
+             Explain which projects depend on it directly and transitively, what is likely to \
+             break, which test suites should be run, and in which order the affected projects \
+             should be updated and released.\n\n\
+             ## Impact analysis\n```json\n{impact}\n```\n\n\
+             ## Workspace state\n```json\n{state}\n```\n"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Outgoing;
+
+    fn server() -> (McpServer, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            tmp.path().join(".meta"),
+            r#"{"projects": {"api": "git@github.com:example/api.git"}}"#,
+        )
+        .unwrap();
+        let server = McpServer::with_meta_dir(
+            Some(tmp.path().to_path_buf()),
+            Outgoing::new(Box::new(std::io::sink())),
+        );
+        (server, tmp)
+    }
+
+    #[test]
+    fn test_list_prompts() {
+        let (server, _tmp) = server();
+        let result = server.handle_list_prompts().unwrap();
+        let names: Vec<&str> = result["prompts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|p| p["name"].as_str())
+            .collect();
+
+        assert_eq!(
+            names,
+            ["prepare_release", "review_changes", "analyze_change_impact"]
+        );
+    }
+
+    #[test]
+    fn test_get_prompt_embeds_live_data() {
+        let (server, _tmp) = server();
+        let result = server
+            .handle_get_prompt(&serde_json::json!({
+                "name": "analyze_change_impact",
+                "arguments": {"project": "api"}
+            }))
+            .unwrap();
+
+        assert_eq!(result["messages"][0]["role"], "user");
+        let text = result["messages"][0]["content"]["text"].as_str().unwrap();
+        assert!(text.contains("'api'"));
+        assert!(text.contains("## Impact analysis"));
+    }
+
+    #[test]
+    fn test_get_prompt_missing_required_argument() {
+        let (server, _tmp) = server();
+        let err = server
+            .handle_get_prompt(&serde_json::json!({"name": "analyze_change_impact"}))
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<RpcError>().unwrap().code, -32602);
+        assert!(err.to_string().contains("project"));
+    }
+}
@@ -0,0 +1,485 @@
+//! MCP resources: read-only workspace data addressable by `meta://` URIs.
+//!
+//! Resources let clients attach workspace context (config, snapshots, READMEs,
+//! workspace state) without spending a tool call on it. Resource templates
+//! additionally expose any file inside a project.
+
+use super::{McpServer, RpcError};
+use anyhow::Result;
+use meta_cli::query::RepoState;
+use serde::Serialize;
+use std::path::{Component, Path, PathBuf};
+
+/// JSON-RPC error code for an unknown resource URI.
+const RESOURCE_NOT_FOUND: i32 = -32002;
+
+const CONFIG_URI: &str = "meta://config";
+pub(crate) const WORKSPACE_STATE_URI: &str = "meta://workspace/state";
+pub(crate) const SNAPSHOT_PREFIX: &str = "meta://snapshot/";
+pub(crate) const PROJECT_PREFIX: &str = "meta://project/";
+const README_SUFFIX: &str = "/readme";
+pub(crate) const STATE_SUFFIX: &str = "/state";
+const FILE_INFIX: &str = "/file/";
+
+/// README file names checked, in order of preference.
+const README_NAMES: &[&str] = &[
+    "README.md",
+    "readme.md",
+    "README",
+    "README.txt",
+    "README.rst",
+];
+
+#[derive(Debug, Serialize)]
+struct Resource {
+    uri: String,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(rename = "mimeType")]
+    mime_type: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ListResourcesResult {
+    resources: Vec<Resource>,
+}
+
+#[derive(Debug, Serialize)]
+struct ResourceTemplate {
+    #[serde(rename = "uriTemplate")]
+    uri_template: String,
+    name: String,
+    description: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ListResourceTemplatesResult {
+    #[serde(rename = "resourceTemplates")]
+    resource_templates: Vec<ResourceTemplate>,
+}
+
+#[derive(Debug, Serialize)]
+struct ResourceContents {
+    uri: String,
+    #[serde(rename = "mimeType")]
+    mime_type: String,
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ReadResourceResult {
+    contents: Vec<ResourceContents>,
+}
+
+impl McpServer {
+    pub(crate) fn handle_list_resources(&self) -> Result<serde_json::Value> {
+        let mut resources = Vec::new();
+
+        if let Some(meta_dir) = self.meta_dir.as_ref() {
+            if let Some(path) = Self::config_file(meta_dir) {
+                resources.push(Resource {
+                    uri: CONFIG_URI.to_string(),
+                    name: "Meta configuration".to_string(),
+                    description: Some(format!("Raw contents of {}", path.display())),
+                    mime_type: config_mime_type(&path).to_string(),
+                });
+
+                resources.push(Resource {
+                    uri: WORKSPACE_STATE_URI.to_string(),
+                    name: "Workspace state".to_string(),
+                    description: Some(
+                        "Summary of dirty/clean counts, branches, and tags across all projects"
+                            .to_string(),
+                    ),
+                    mime_type: "application/json".to_string(),
+                });
+            }
+
+            for (name, _) in snapshot_files(&self.snapshots_dir(meta_dir)) {
+                resources.push(Resource {
+                    uri: format!("{SNAPSHOT_PREFIX}{name}"),
+                    name: format!("Snapshot: {name}"),
+                    description: None,
+                    mime_type: "application/json".to_string(),
+                });
+            }
+
+            if let Ok(projects) = self.load_projects(meta_dir) {
+                for project in &projects {
+                    let project_path = meta_dir.join(&project.path);
+                    if !project_path.exists() {
+                        continue;
+                    }
+
+                    resources.push(Resource {
+                        uri: format!("{PROJECT_PREFIX}{}{STATE_SUFFIX}", project.name),
+                        name: format!("{} git state", project.name),
+                        description: Some(
+                            "Branch, dirty state and ahead/behind counts; subscribe for change notifications"
+                                .to_string(),
+                        ),
+                        mime_type: "application/json".to_string(),
+                    });
+
+                    if let Some(readme) = find_readme(&project_path) {
+                        resources.push(Resource {
+                            uri: format!("{PROJECT_PREFIX}{}{README_SUFFIX}", project.name),
+                            name: format!("{} README", project.name),
+                            description: None,
+                            mime_type: file_mime_type(&readme).to_string(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(serde_json::to_value(ListResourcesResult { resources })?)
+    }
+
+    pub(crate) fn handle_list_resource_templates(&self) -> Result<serde_json::Value> {
+        let resource_templates = vec![
+            ResourceTemplate {
+                uri_template: format!("{PROJECT_PREFIX}{{project}}{FILE_INFIX}{{path}}"),
+                name: "Project file".to_string(),
+                description: "Any file inside a project, by path relative to the project root"
+                    .to_string(),
+            },
+            ResourceTemplate {
+                uri_template: format!("{PROJECT_PREFIX}{{project}}{STATE_SUFFIX}"),
+                name: "Project git state".to_string(),
+                description: "Git state of a project; subscribe for change notifications"
+                    .to_string(),
+            },
+            ResourceTemplate {
+                uri_template: format!("{PROJECT_PREFIX}{{project}}{README_SUFFIX}"),
+                name: "Project README".to_string(),
+                description: "The README of a project".to_string(),
+            },
+            ResourceTemplate {
+                uri_template: format!("{SNAPSHOT_PREFIX}{{name}}"),
+                name: "Workspace snapshot".to_string(),
+                description: "A snapshot saved with meta_snapshot_create".to_string(),
+            },
+        ];
+
+        Ok(serde_json::to_value(ListResourceTemplatesResult {
+            resource_templates,
+        })?)
+    }
+
+    pub(crate) fn handle_read_resource(
+        &self,
+        params: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let uri = params
+            .get("uri")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| RpcError::new(-32602, "Missing 'uri' parameter"))?;
+
+        let (mime_type, text) = self.read_resource(uri)?;
+
+        let result = ReadResourceResult {
+            contents: vec![ResourceContents {
+                uri: uri.to_string(),
+                mime_type,
+                text,
+            }],
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+
+    /// Resolve a resource URI to its MIME type and text content.
+    pub(crate) fn read_resource(&self, uri: &str) -> Result<(String, String)> {
+        let not_found = || RpcError::new(RESOURCE_NOT_FOUND, format!("Resource not found: {uri}"));
+
+        let meta_dir = self
+            .meta_dir
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No meta repository found"))?;
+
+        if uri == CONFIG_URI {
+            let path = Self::config_file(meta_dir).ok_or_else(not_found)?;
+            let content = std::fs::read_to_string(&path)?;
+            return Ok((config_mime_type(&path).to_string(), content));
+        }
+
+        if uri == WORKSPACE_STATE_URI {
+            let workspace_state =
+                self.workspace_state(meta_dir, self.concurrency(&serde_json::json!({})))?;
+            return Ok((
+                "application/json".to_string(),
+                serde_json::to_string_pretty(&workspace_state)?,
+            ));
+        }
+
+        if let Some(name) = uri.strip_prefix(SNAPSHOT_PREFIX) {
+            let (_, path) = snapshot_files(&self.snapshots_dir(meta_dir))
+                .into_iter()
+                .find(|(stem, _)| stem == name)
+                .ok_or_else(not_found)?;
+            let content = std::fs::read_to_string(&path)?;
+            return Ok(("application/json".to_string(), content));
+        }
+
+        if let Some(rest) = uri.strip_prefix(PROJECT_PREFIX) {
+            // Project names may contain '/', so match against the known names
+            // instead of splitting the URI on separators.
+            let projects = self.load_projects(meta_dir)?;
+            for project in &projects {
+                let tail = match rest.strip_prefix(project.name.as_str()) {
+                    Some(tail) => tail,
+                    None => continue,
+                };
+                let project_root = meta_dir.join(&project.path);
+
+                if tail == STATE_SUFFIX {
+                    let state = RepoState::collect(&project.name, &project_root, &project.tags)?;
+                    return Ok((
+                        "application/json".to_string(),
+                        serde_json::to_string_pretty(&state)?,
+                    ));
+                }
+
+                if tail == README_SUFFIX {
+                    let readme = find_readme(&project_root).ok_or_else(not_found)?;
+                    let content = std::fs::read_to_string(&readme)?;
+                    return Ok((file_mime_type(&readme).to_string(), content));
+                }
+
+                if let Some(relative) = tail.strip_prefix(FILE_INFIX) {
+                    let path = resolve_within(&project_root, &percent_decode(relative))?
+                        .ok_or_else(not_found)?;
+                    if !path.is_file() {
+                        return Err(not_found().into());
+                    }
+                    let content = std::fs::read_to_string(&path).map_err(|_| {
+                        RpcError::new(-32602, format!("Not a UTF-8 text file: {uri}"))
+                    })?;
+                    return Ok((file_mime_type(&path).to_string(), content));
+                }
+            }
+        }
+
+        Err(not_found().into())
+    }
+}
+
+/// List `(name, path)` for every snapshot file in `snapshots_dir`, sorted by
+/// name.
+///
+/// The name is the file stem, which is what `meta_snapshot_restore` resolves.
+pub(crate) fn snapshot_files(snapshots_dir: &Path) -> Vec<(String, PathBuf)> {
+    let mut files: Vec<(String, PathBuf)> = std::fs::read_dir(snapshots_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map(|e| e == "json").unwrap_or(false))
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_string_lossy().to_string();
+            Some((stem, path))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn find_readme(project_path: &Path) -> Option<PathBuf> {
+    README_NAMES
+        .iter()
+        .map(|name| project_path.join(name))
+        .find(|path| path.is_file())
+}
+
+fn config_mime_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("yaml") | Some("yml") => "application/yaml",
+        _ => "application/json",
+    }
+}
+
+fn file_mime_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("md") => "text/markdown",
+        Some("json") => "application/json",
+        Some("yaml") | Some("yml") => "application/yaml",
+        Some("toml") => "application/toml",
+        _ => "text/plain",
+    }
+}
+
+/// Resolve `relative` against `root` without letting it escape `root`.
+///
+/// `..`, absolute paths and drive prefixes that would leave the root are
+/// rejected as invalid params. Returns `Ok(None)` when the path is well-formed
+/// but does not exist. Symlinks are followed and must also stay inside the root.
+fn resolve_within(root: &Path, relative: &str) -> Result<Option<PathBuf>> {
+    let escapes = || RpcError::new(-32602, format!("Path escapes project root: {relative}"));
+
+    let mut normalized = PathBuf::new();
+    for component in Path::new(relative).components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return Err(escapes().into());
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return Err(escapes().into()),
+        }
+    }
+
+    let (root, candidate) = match (root.canonicalize(), root.join(&normalized).canonicalize()) {
+        (Ok(root), Ok(candidate)) => (root, candidate),
+        _ => return Ok(None),
+    };
+    if !candidate.starts_with(&root) {
+        return Err(escapes().into());
+    }
+    Ok(Some(candidate))
+}
+
+/// Decode `%XX` escapes so template-expanded paths match files on disk.
+pub(crate) fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit()
+        {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("00");
+            decoded.push(u8::from_str_radix(hex, 16).unwrap_or(0));
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server_with_workspace() -> (McpServer, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            tmp.path().join(".meta"),
+            r#"{"projects": {"api": "git@github.com:example/api.git"}}"#,
+        )
+        .unwrap();
+        std::fs::create_dir_all(tmp.path().join("api")).unwrap();
+        std::fs::write(tmp.path().join("api/README.md"), "# API\n").unwrap();
+        std::fs::create_dir_all(tmp.path().join(".meta-snapshots")).unwrap();
+        std::fs::write(
+            tmp.path().join(".meta-snapshots/before-release.json"),
+            r#"{"name": "before-release", "projects": []}"#,
+        )
+        .unwrap();
+        let server = McpServer::with_meta_dir(
+            Some(tmp.path().to_path_buf()),
+            crate::Outgoing::new(Box::new(std::io::sink())),
+        );
+        (server, tmp)
+    }
+
+    fn uris(server: &McpServer) -> Vec<String> {
+        let result = server.handle_list_resources().unwrap();
+        result["resources"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["uri"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_list_resources() {
+        let (server, _tmp) = server_with_workspace();
+        let uris = uris(&server);
+
+        assert!(uris.contains(&"meta://config".to_string()));
+        assert!(uris.contains(&"meta://workspace/state".to_string()));
+        assert!(uris.contains(&"meta://snapshot/before-release".to_string()));
+        assert!(uris.contains(&"meta://project/api/readme".to_string()));
+        assert!(uris.contains(&"meta://project/api/state".to_string()));
+    }
+
+    #[test]
+    fn test_read_resources() {
+        let (server, _tmp) = server_with_workspace();
+
+        let readme = server
+            .handle_read_resource(&serde_json::json!({"uri": "meta://project/api/readme"}))
+            .unwrap();
+        assert_eq!(readme["contents"][0]["text"], "# API\n");
+        assert_eq!(readme["contents"][0]["mimeType"], "text/markdown");
+
+        let snapshot = server
+            .handle_read_resource(&serde_json::json!({"uri": "meta://snapshot/before-release"}))
+            .unwrap();
+        assert!(snapshot["contents"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("before-release"));
+    }
+
+    #[test]
+    fn test_read_project_file_template() {
+        let (server, tmp) = server_with_workspace();
+        std::fs::create_dir_all(tmp.path().join("api/src")).unwrap();
+        std::fs::write(tmp.path().join("api/src/lib.rs"), "pub fn api() {}\n").unwrap();
+
+        let file = server
+            .handle_read_resource(&serde_json::json!({"uri": "meta://project/api/file/src/lib.rs"}))
+            .unwrap();
+        assert_eq!(file["contents"][0]["text"], "pub fn api() {}\n");
+
+        let encoded = server
+            .handle_read_resource(
+                &serde_json::json!({"uri": "meta://project/api/file/src%2Flib.rs"}),
+            )
+            .unwrap();
+        assert_eq!(encoded["contents"][0]["text"], "pub fn api() {}\n");
+    }
+
+    #[test]
+    fn test_project_file_cannot_escape_root() {
+        let (server, _tmp) = server_with_workspace();
+
+        for uri in [
+            "meta://project/api/file/../.meta",
+            "meta://project/api/file/src/../../.meta",
+            "meta://project/api/file/%2E%2E/.meta",
+            "meta://project/api/file//etc/passwd",
+        ] {
+            let err = server
+                .handle_read_resource(&serde_json::json!({ "uri": uri }))
+                .unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<RpcError>().unwrap().code,
+                -32602,
+                "{uri}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_unknown_resource() {
+        let (server, _tmp) = server_with_workspace();
+
+        let err = server
+            .handle_read_resource(&serde_json::json!({"uri": "meta://project/missing/readme"}))
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<RpcError>().unwrap().code,
+            RESOURCE_NOT_FOUND
+        );
+    }
+}
@@ -0,0 +1,280 @@
+//! MCP roots: choosing workspaces from the client's roots.
+//!
+//! A server launched outside the workspace would otherwise answer every tool
+//! call with "No meta repository found". Once the client is initialized, and
+//! again whenever it reports `notifications/roots/list_changed`, the server
+//! asks for `roots/list` and registers every root that lies in a meta
+//! workspace, the first one becoming the default. If none does, the configured
+//! workspaces are used.
+
+use super::resources::percent_decode;
+use super::{find_meta_dir_from, ClientResponse, McpServer};
+use crate::logging::LogLevel;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How long to wait for the client to answer a request.
+const CLIENT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Requests sent to the client, by id, with the channel their response goes to.
+pub(crate) type PendingClientRequests = Arc<Mutex<HashMap<String, Sender<ClientResponse>>>>;
+
+impl McpServer {
+    /// Send `method` to the client and wait for its result.
+    ///
+    /// The response arrives on the same input as client requests, so this must
+    /// not be called from the thread that reads them.
+    pub(crate) fn request_client(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        let id = format!("meta-mcp-{}", NEXT_ID.fetch_add(1, Ordering::SeqCst));
+
+        let (sender, receiver) = mpsc::channel();
+        self.client_requests
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Client requests lock poisoned"))?
+            .insert(id.clone(), sender);
+
+        let response = self
+            .outgoing
+            .send(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": method,
+                "params": params,
+            }))
+            .and_then(|_| {
+                receiver
+                    .recv_timeout(CLIENT_REQUEST_TIMEOUT)
+                    .map_err(|_| anyhow::anyhow!("Client did not answer {method}"))
+            });
+        if let Ok(mut pending) = self.client_requests.lock() {
+            pending.remove(&id);
+        }
+
+        let response = response?;
+        match (response.result, response.error) {
+            (_, Some(error)) => anyhow::bail!("Client failed {method}: {error}"),
+            (Some(result), None) => Ok(result),
+            (None, None) => anyhow::bail!("Client sent an empty response to {method}"),
+        }
+    }
+
+    /// Hand a response from the client to the request waiting for it.
+    pub(crate) fn handle_client_response(&self, response: ClientResponse) {
+        let id = match response.id.as_str() {
+            Some(id) => id.to_string(),
+            None => response.id.to_string(),
+        };
+        let waiting = self
+            .client_requests
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.remove(&id));
+
+        match waiting {
+            Some(sender) => {
+                let _ = sender.send(response);
+            }
+            None => self.log(
+                LogLevel::Debug,
+                format!("Ignoring response to unknown request {id}"),
+            ),
+        }
+    }
+
+    /// Re-resolve the workspaces from the client's roots in the background, if
+    /// the client supports roots.
+    pub(crate) fn refresh_roots(&self) {
+        if !self.client_roots.load(Ordering::SeqCst) {
+            return;
+        }
+        let server = self.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = server.resolve_workspace_from_roots() {
+                server.log(
+                    LogLevel::Warning,
+                    format!("Could not use client roots: {e}"),
+                );
+            }
+        });
+    }
+
+    fn resolve_workspace_from_roots(&self) -> Result<()> {
+        let result = self.request_client("roots/list", serde_json::json!({}))?;
+        let found: Vec<PathBuf> = result
+            .get("roots")
+            .and_then(|roots| roots.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|root| root_path(root.get("uri")?.as_str()?))
+            .filter_map(|path| find_meta_dir_from(&path))
+            .collect();
+
+        if found.is_empty() {
+            self.log(
+                LogLevel::Notice,
+                "No meta workspace found in the client's roots; using the configured ones",
+            );
+        } else {
+            let listed: Vec<String> = found.iter().map(|d| d.display().to_string()).collect();
+            self.log(
+                LogLevel::Info,
+                format!("Using workspaces from client roots: {}", listed.join(", ")),
+            );
+        }
+        self.workspaces
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Workspaces lock poisoned"))?
+            .set_from_roots(found);
+        Ok(())
+    }
+}
+
+/// Local path of a `file://` root URI.
+///
+/// Besides `file:///work/ws`, accepts the forms Windows clients send:
+/// `file:///C:/work`, `file:///c%3A/work`, the malformed `file://C:\work`, and
+/// `file://server/share` for UNC shares.
+fn root_path(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    let rest = rest
+        .strip_prefix("localhost")
+        .filter(|path| path.starts_with('/'))
+        .unwrap_or(rest);
+    let path = percent_decode(rest);
+
+    let without_slash = path.strip_prefix('/').unwrap_or(&path);
+    if let Some(drive) = drive_path(without_slash) {
+        return Some(drive);
+    }
+    if path.starts_with('/') {
+        return Some(PathBuf::from(path));
+    }
+    // Any other host names a share only Windows can open
+    if cfg!(windows) {
+        return Some(PathBuf::from(format!(r"\\{}", path.replace('/', r"\"))));
+    }
+    None
+}
+
+/// `C:/work` or `c:\work` as a path with an upper-case drive letter, which is
+/// how Windows spells paths found on disk.
+fn drive_path(path: &str) -> Option<PathBuf> {
+    let bytes = path.as_bytes();
+    let is_drive = bytes.len() >= 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes.len() == 2 || bytes[2] == b'/' || bytes[2] == b'\\');
+    if !is_drive {
+        return None;
+    }
+    Some(PathBuf::from(format!(
+        "{}{}",
+        path[..1].to_ascii_uppercase(),
+        &path[1..]
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::SharedBuffer;
+    use crate::Outgoing;
+    use std::path::Path;
+
+    #[test]
+    fn test_root_path() {
+        assert_eq!(
+            root_path("file:///work/ws"),
+            Some(PathBuf::from("/work/ws"))
+        );
+        assert_eq!(
+            root_path("file://localhost/my%20ws"),
+            Some(PathBuf::from("/my ws"))
+        );
+        assert_eq!(root_path("file:///C:/work"), Some(PathBuf::from("C:/work")));
+        assert_eq!(
+            root_path("file:///c%3A/my%20work"),
+            Some(PathBuf::from("C:/my work"))
+        );
+        assert_eq!(
+            root_path(r"file://C:\Users\me"),
+            Some(PathBuf::from(r"C:\Users\me"))
+        );
+        if cfg!(windows) {
+            assert_eq!(
+                root_path("file://server/share/ws"),
+                Some(PathBuf::from(r"\\server\share\ws"))
+            );
+        } else {
+            assert_eq!(root_path("file://server/share"), None);
+        }
+        assert_eq!(root_path("https://example.com"), None);
+    }
+
+    /// A `file://` URI for `path` as clients build it: forward slashes, a
+    /// leading slash before Windows drives, and everything else escaped.
+    fn file_uri(path: &Path) -> String {
+        let path = path.to_str().unwrap().replace('\\', "/");
+        let mut uri = String::from("file://");
+        if !path.starts_with('/') {
+            uri.push('/');
+        }
+        for byte in path.bytes() {
+            match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                    uri.push(byte as char)
+                }
+                _ => uri.push_str(&format!("%{byte:02X}")),
+            }
+        }
+        uri
+    }
+
+    #[test]
+    fn test_workspace_follows_client_roots() {
+        let tmp = tempfile::tempdir().unwrap();
+        let workspace = tmp.path().join("ws");
+        std::fs::create_dir_all(workspace.join("api")).unwrap();
+        std::fs::write(workspace.join(".meta"), r#"{"projects": {"api": "x"}}"#).unwrap();
+
+        let buffer = SharedBuffer::default();
+        let server = McpServer::with_meta_dir(None, Outgoing::new(Box::new(buffer.clone())));
+        let resolver = {
+            let server = server.clone();
+            std::thread::spawn(move || server.resolve_workspace_from_roots())
+        };
+
+        // Answer the roots/list request the server sends
+        let request = (0..200)
+            .find_map(|_| {
+                std::thread::sleep(Duration::from_millis(10));
+                buffer.messages().into_iter().next()
+            })
+            .expect("roots/list was not sent");
+        assert_eq!(request["method"], "roots/list");
+        server.handle_client_response(ClientResponse {
+            id: request["id"].clone(),
+            result: Some(serde_json::json!({
+                "roots": [
+                    {"uri": file_uri(&tmp.path().join("elsewhere"))},
+                    {"uri": file_uri(&workspace.join("api"))},
+                ]
+            })),
+            error: None,
+        });
+        resolver.join().unwrap().unwrap();
+
+        assert_eq!(server.meta_dir, None);
+        assert_eq!(server.with_current_workspace().meta_dir, Some(workspace));
+    }
+}
@@ -0,0 +1,429 @@
+//! Per-workspace settings for this server.
+//!
+//! A workspace can tune the server with a `.meta-mcp.yaml` (or `.yml`) next to
+//! its `.meta`, or with an `mcp` section inside the meta config itself; the
+//! separate file wins when both exist. For example:
+//!
+//! ```yaml
+//! read_only: true          # only read-only tools, as with --read-only
+//! read_only_tools: [meta_plugin_release_status]  # also offered when read-only
+//! concurrency: 4
+//! timeouts:                 # seconds; `default` applies to every other tool
+//!   default: 120
+//!   meta_run_tests: 3600
+//! disabled_tools: [meta_git_push, "meta_plugin_*"]
+//! max_output_bytes: 65536   # per stream of each process; the end is kept
+//! snapshot_dir: .snapshots
+//! test_commands:            # by project tag, run with `sh -c`
+//!   frontend: npm run test:ci
+//! build_commands:
+//!   rust: cargo build --locked
+//! scripts:                  # offered as meta_script_<name> tools
+//!   lint: npm run lint
+//!   audit: { command: npm audit, description: Audit dependencies }
+//! ```
+//!
+//! The files are checked for changes whenever the settings are needed, so an
+//! edit applies to the next request without restarting anything. Settings that
+//! cannot be read never loosen anything: the last good ones stay in effect, and
+//! without any, tool calls are refused until the error is fixed.
+
+use super::logging::LogLevel;
+use super::McpServer;
+use anyhow::{Context, Result};
+use meta_core::config::ProjectInfo;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+const CONFIG_FILES: &[&str] = &[".meta-mcp.yaml", ".meta-mcp.yml"];
+
+/// Settings of one workspace. Everything is optional.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct ServerConfig {
+    /// Offer only read-only tools, like `--read-only`.
+    pub(crate) read_only: bool,
+    /// Script and plugin tools to offer in read-only mode as well, which
+    /// otherwise never are. Built-in tools are unaffected, and so is a server
+    /// started with `--read-only`.
+    pub(crate) read_only_tools: Vec<String>,
+    /// Projects worked on at once when a call does not pass `concurrency`.
+    pub(crate) concurrency: Option<usize>,
+    /// Process time limits in seconds by tool name, with `default` for the rest.
+    pub(crate) timeouts: BTreeMap<String, u64>,
+    /// Tools to offer. Empty means all of them.
+    pub(crate) enabled_tools: Vec<String>,
+    /// Tools never to offer, even when enabled.
+    pub(crate) disabled_tools: Vec<String>,
+    /// Most bytes kept of each process's stdout and of its stderr.
+    pub(crate) max_output_bytes: Option<usize>,
+    /// Where snapshots are stored, relative to the workspace and inside it.
+    pub(crate) snapshot_dir: Option<PathBuf>,
+    /// Commands `meta_build` runs instead of `cargo build`, by project tag.
+    /// They are run as given, whatever `release` the call passes.
+    pub(crate) build_commands: BTreeMap<String, String>,
+    /// Commands `meta_run_tests` runs instead of the detected one, by project tag.
+    pub(crate) test_commands: BTreeMap<String, String>,
+    /// Commands offered as tools of their own, by name.
+    pub(crate) scripts: BTreeMap<String, Script>,
+    /// Why the settings could not be read, when there were no good ones to
+    /// fall back on. Tool calls are refused while this is set.
+    #[serde(skip)]
+    pub(crate) error: Option<String>,
+}
+
+/// A workspace script: its command alone, or with a description for the tool.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum Script {
+    Command(String),
+    Described {
+        command: String,
+        #[serde(default)]
+        description: Option<String>,
+    },
+}
+
+impl Script {
+    pub(crate) fn command(&self) -> &str {
+        match self {
+            Script::Command(command) | Script::Described { command, .. } => command,
+        }
+    }
+
+    pub(crate) fn description(&self) -> Option<&str> {
+        match self {
+            Script::Command(_) => None,
+            Script::Described { description, .. } => description.as_deref(),
+        }
+    }
+}
+
+/// Settings as last read for a workspace, with the modification times of the
+/// files they were read from.
+#[derive(Debug, Clone)]
+pub(crate) struct CachedConfig {
+    stamp: Vec<Option<SystemTime>>,
+    config: ServerConfig,
+}
+
+impl ServerConfig {
+    /// Read the settings of the workspace in `meta_dir`.
+    pub(crate) fn load(meta_dir: &Path) -> Result<Self> {
+        if let Some(path) = CONFIG_FILES
+            .iter()
+            .map(|name| meta_dir.join(name))
+            .find(|path| path.exists())
+        {
+            let text = std::fs::read_to_string(&path)?;
+            return serde_yaml::from_str::<Option<Self>>(&text)
+                .map(Option::unwrap_or_default)
+                .map_err(anyhow::Error::from)
+                .and_then(Self::validated)
+                .with_context(|| format!("Invalid server config in {}", path.display()));
+        }
+
+        let path = match McpServer::config_file(meta_dir) {
+            Some(path) => path,
+            None => return Ok(Self::default()),
+        };
+        let text = std::fs::read_to_string(&path)?;
+        let config: serde_json::Value = match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&text)?,
+            _ => serde_json::from_str(&text)?,
+        };
+        match config.get("mcp") {
+            Some(section) => serde_json::from_value(section.clone())
+                .map_err(anyhow::Error::from)
+                .and_then(Self::validated)
+                .with_context(|| format!("Invalid 'mcp' section in {}", path.display())),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Refuse settings that reach outside the workspace.
+    fn validated(self) -> Result<Self> {
+        if let Some(dir) = &self.snapshot_dir {
+            let inside = dir
+                .components()
+                .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+            if !inside {
+                anyhow::bail!(
+                    "snapshot_dir '{}' must be a relative path inside the workspace",
+                    dir.display()
+                );
+            }
+        }
+        Ok(self)
+    }
+
+    /// Whether `tool` may be offered.
+    pub(crate) fn tool_enabled(&self, tool: &str) -> bool {
+        let listed = |patterns: &[String]| patterns.iter().any(|p| matches_tool(p, tool));
+        (self.enabled_tools.is_empty() || listed(&self.enabled_tools))
+            && !listed(&self.disabled_tools)
+    }
+
+    /// Whether `read_only_tools` lists `tool`.
+    pub(crate) fn read_only_tool_listed(&self, tool: &str) -> bool {
+        self.read_only_tools.iter().any(|p| matches_tool(p, tool))
+    }
+
+    /// Process time limit for `tool` in seconds, if the workspace sets one.
+    pub(crate) fn timeout_secs(&self, tool: &str) -> Option<u64> {
+        self.timeouts
+            .get(tool)
+            .or_else(|| self.timeouts.get("default"))
+            .copied()
+    }
+
+    /// The configured build command for `project`, from the first of its tags
+    /// that has one.
+    pub(crate) fn build_command(&self, project: &ProjectInfo) -> Option<&str> {
+        tagged_command(&self.build_commands, project)
+    }
+
+    /// The configured test command for `project`, from the first of its tags
+    /// that has one.
+    pub(crate) fn test_command(&self, project: &ProjectInfo) -> Option<&str> {
+        tagged_command(&self.test_commands, project)
+    }
+}
+
+impl McpServer {
+    /// The settings of this handle's workspace. They are read again when the
+    /// files they come from change. An unreadable config is reported and the
+    /// last good settings are kept; without any, the settings carry the error.
+    pub(crate) fn server_config(&self) -> ServerConfig {
+        let meta_dir = match &self.meta_dir {
+            Some(meta_dir) => meta_dir,
+            None => return ServerConfig::default(),
+        };
+        let stamp = config_stamp(meta_dir);
+        let cached = self
+            .server_configs
+            .lock()
+            .ok()
+            .and_then(|cache| cache.get(meta_dir).cloned());
+        if let Some(cached) = &cached {
+            if cached.stamp == stamp {
+                return cached.config.clone();
+            }
+        }
+
+        let last_good = cached
+            .map(|cached| cached.config)
+            .filter(|config| config.error.is_none());
+        let config = match (ServerConfig::load(meta_dir), last_good) {
+            (Ok(config), _) => config,
+            (Err(e), Some(last_good)) => {
+                self.log(
+                    LogLevel::Error,
+                    format!("{e:#}; keeping the previous settings"),
+                );
+                last_good
+            }
+            (Err(e), None) => {
+                self.log(
+                    LogLevel::Error,
+                    format!("{e:#}; refusing tool calls until it is fixed"),
+                );
+                ServerConfig {
+                    error: Some(format!("{e:#}")),
+                    ..ServerConfig::default()
+                }
+            }
+        };
+        if let Ok(mut cache) = self.server_configs.lock() {
+            cache.insert(
+                meta_dir.clone(),
+                CachedConfig {
+                    stamp,
+                    config: config.clone(),
+                },
+            );
+        }
+        config
+    }
+
+    /// Where this workspace keeps its snapshots.
+    pub(crate) fn snapshots_dir(&self, meta_dir: &Path) -> PathBuf {
+        let configured = self.server_config().snapshot_dir;
+        meta_dir.join(
+            configured
+                .as_deref()
+                .unwrap_or(Path::new(".meta-snapshots")),
+        )
+    }
+}
+
+/// Modification times of every file settings can come from, to notice edits.
+fn config_stamp(meta_dir: &Path) -> Vec<Option<SystemTime>> {
+    CONFIG_FILES
+        .iter()
+        .map(|name| meta_dir.join(name))
+        .chain(McpServer::config_file(meta_dir))
+        .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// A tool name pattern: an exact name, or a prefix followed by `*`.
+fn matches_tool(pattern: &str, tool: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => tool.starts_with(prefix),
+        None => pattern == tool,
+    }
+}
+
+fn tagged_command<'a>(
+    commands: &'a BTreeMap<String, String>,
+    project: &ProjectInfo,
+) -> Option<&'a str> {
+    project
+        .tags
+        .iter()
+        .find_map(|tag| commands.get(tag))
+        .map(String::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Outgoing;
+
+    #[test]
+    fn test_load_from_meta_section_and_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert_eq!(
+            ServerConfig::load(tmp.path()).unwrap(),
+            ServerConfig::default()
+        );
+
+        std::fs::write(
+            tmp.path().join(".meta"),
+            r#"{"projects": {}, "mcp": {"concurrency": 2, "disabled_tools": ["meta_git_push"]}}"#,
+        )
+        .unwrap();
+        let config = ServerConfig::load(tmp.path()).unwrap();
+        assert_eq!(config.concurrency, Some(2));
+        assert!(!config.tool_enabled("meta_git_push"));
+        assert!(config.tool_enabled("meta_git_pull"));
+
+        // A separate file takes precedence over the section
+        std::fs::write(
+            tmp.path().join(".meta-mcp.yaml"),
+            "enabled_tools: [\"meta_git_*\"]\ntimeouts:\n  default: 60\n  meta_run_tests: 900\ntest_commands:\n  frontend: npm run test:ci\n",
+        )
+        .unwrap();
+        let config = ServerConfig::load(tmp.path()).unwrap();
+        assert_eq!(config.concurrency, None);
+        assert!(config.tool_enabled("meta_git_push"));
+        assert!(!config.tool_enabled("meta_build"));
+        assert_eq!(config.timeout_secs("meta_run_tests"), Some(900));
+        assert_eq!(config.timeout_secs("meta_build"), Some(60));
+
+        let project = ProjectInfo {
+            name: "web".to_string(),
+            path: "web".to_string(),
+            repo: None,
+            tags: vec!["app".to_string(), "frontend".to_string()],
+        };
+        assert_eq!(config.test_command(&project), Some("npm run test:ci"));
+        assert_eq!(config.build_command(&project), None);
+
+        std::fs::write(tmp.path().join(".meta-mcp.yaml"), "concurrncy: 2\n").unwrap();
+        let err = ServerConfig::load(tmp.path()).unwrap_err();
+        assert!(format!("{err:#}").contains("concurrncy"));
+
+        std::fs::write(
+            tmp.path().join(".meta-mcp.yaml"),
+            "scripts:\n  lint: npm run lint\n  audit: { command: npm audit, description: Audit }\n",
+        )
+        .unwrap();
+        let config = ServerConfig::load(tmp.path()).unwrap();
+        assert_eq!(config.scripts["lint"].command(), "npm run lint");
+        assert_eq!(config.scripts["lint"].description(), None);
+        assert_eq!(config.scripts["audit"].command(), "npm audit");
+        assert_eq!(config.scripts["audit"].description(), Some("Audit"));
+
+        std::fs::write(
+            tmp.path().join(".meta-mcp.yaml"),
+            "scripts:\n  broken: { description: no command }\n",
+        )
+        .unwrap();
+        assert!(ServerConfig::load(tmp.path()).is_err());
+
+        for dir in ["/tmp/elsewhere", "../elsewhere", "backups/../../elsewhere"] {
+            std::fs::write(
+                tmp.path().join(".meta-mcp.yaml"),
+                format!("snapshot_dir: {dir}\n"),
+            )
+            .unwrap();
+            let err = ServerConfig::load(tmp.path()).unwrap_err();
+            assert!(format!("{err:#}").contains("inside the workspace"), "{dir}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_config_never_loosens_settings() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join(".meta"), r#"{"projects": {}}"#).unwrap();
+        let path = tmp.path().join(".meta-mcp.yaml");
+        let server = McpServer::with_meta_dir(
+            Some(tmp.path().to_path_buf()),
+            Outgoing::new(Box::new(std::io::sink())),
+        );
+        let edit = |text: &str, age: u64| {
+            std::fs::write(&path, text).unwrap();
+            let file = std::fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(SystemTime::now() + std::time::Duration::from_secs(age))
+                .unwrap();
+        };
+
+        // Broken from the start: tool calls are refused
+        edit("read_only: true\ndisabled_tols: [meta_git_push]\n", 0);
+        assert!(server.server_config().error.is_some());
+        let err = server
+            .call_tool("meta_list_projects", &serde_json::json!({}))
+            .unwrap_err();
+        assert!(err.to_string().contains("disabled_tols"));
+
+        // Fixed, then broken again: the good settings stay
+        edit("read_only: true\n", 5);
+        assert!(server.server_config().read_only);
+        edit("read_only: true\nconcurrncy: 2\n", 10);
+        let config = server.server_config();
+        assert!(config.read_only);
+        assert!(config.error.is_none());
+    }
+
+    #[test]
+    fn test_config_changes_are_picked_up() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join(".meta"), r#"{"projects": {}}"#).unwrap();
+        let server = McpServer::with_meta_dir(
+            Some(tmp.path().to_path_buf()),
+            Outgoing::new(Box::new(std::io::sink())),
+        );
+        assert_eq!(
+            server.snapshots_dir(tmp.path()),
+            tmp.path().join(".meta-snapshots")
+        );
+
+        let path = tmp.path().join(".meta-mcp.yaml");
+        std::fs::write(&path, "snapshot_dir: backups\n").unwrap();
+        assert_eq!(server.snapshots_dir(tmp.path()), tmp.path().join("backups"));
+
+        // Make sure the edit gets a different modification time
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        std::fs::write(&path, "snapshot_dir: elsewhere\n").unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(
+            server.snapshots_dir(tmp.path()),
+            tmp.path().join("elsewhere")
+        );
+    }
+}
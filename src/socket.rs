@@ -0,0 +1,196 @@
+//! Unix domain socket transport, selected with `--socket PATH`.
+//!
+//! Lets several local clients (say, an editor and a background agent) share
+//! one server per workspace instead of each starting its own. Every connection
+//! is an independent session speaking the same newline-delimited JSON-RPC as
+//! stdio.
+
+use super::plugins::SharedPlugins;
+use super::{McpServer, Outgoing};
+use anyhow::{Context, Result};
+use std::io::BufReader;
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+
+/// Serve MCP on a Unix socket at `path` until the process is stopped.
+pub(crate) fn serve(path: &Path, workspaces: Vec<PathBuf>, read_only: bool) -> Result<()> {
+    remove_stale_socket(path)?;
+    let listener =
+        bind_private(path).with_context(|| format!("Failed to listen on {}", path.display()))?;
+    eprintln!("meta-mcp listening on {}", path.display());
+
+    serve_on(listener, workspaces, read_only);
+    Ok(())
+}
+
+fn serve_on(listener: UnixListener, workspaces: Vec<PathBuf>, read_only: bool) {
+    // Installed plugins are listed for all connections at once
+    let plugins = SharedPlugins::default();
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Failed to accept connection: {e}");
+                continue;
+            }
+        };
+        let workspaces = workspaces.clone();
+        let plugins = plugins.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = serve_connection(stream, workspaces, read_only, plugins) {
+                eprintln!("Socket connection failed: {e}");
+            }
+        });
+    }
+}
+
+fn serve_connection(
+    stream: UnixStream,
+    workspaces: Vec<PathBuf>,
+    read_only: bool,
+    plugins: SharedPlugins,
+) -> Result<()> {
+    let server = McpServer {
+        read_only,
+        plugins,
+        ..McpServer::with_workspaces(workspaces, Outgoing::new(Box::new(stream.try_clone()?)))
+    };
+    server.serve(BufReader::new(stream))
+}
+
+/// Bind a socket at `path` that only this user can connect to.
+///
+/// The socket grants full access to the workspace. It is bound inside a fresh
+/// 0700 directory, restricted to 0600 and only then moved to `path`, so there
+/// is no moment at which other users could connect to it.
+fn bind_private(path: &Path) -> Result<UnixListener> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("{} is not a file path", path.display()))?;
+    let private_dir = parent.join(format!(
+        ".{}.{}",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+    std::fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&private_dir)
+        .with_context(|| format!("Failed to create {}", private_dir.display()))?;
+
+    let bound = private_dir.join(file_name);
+    let listener = UnixListener::bind(&bound).and_then(|listener| {
+        std::fs::set_permissions(&bound, std::fs::Permissions::from_mode(0o600))?;
+        std::fs::rename(&bound, path)?;
+        Ok(listener)
+    });
+    let _ = std::fs::remove_file(&bound);
+    let _ = std::fs::remove_dir(&private_dir);
+    Ok(listener?)
+}
+
+/// Remove a socket file left behind by a server that is no longer running.
+/// Refuses to touch a live socket or anything that is not a socket.
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return Ok(()),
+    };
+    if !metadata.file_type().is_socket() {
+        anyhow::bail!("{} exists and is not a socket", path.display());
+    }
+    if UnixStream::connect(path).is_ok() {
+        anyhow::bail!("Another server is already listening on {}", path.display());
+    }
+    std::fs::remove_file(path)
+        .with_context(|| format!("Failed to remove stale socket {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, Write};
+
+    fn request(
+        stream: &mut UnixStream,
+        reader: &mut impl BufRead,
+        message: &str,
+    ) -> serde_json::Value {
+        writeln!(stream, "{message}").unwrap();
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[test]
+    fn test_connections_are_separate_sessions() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("meta.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        std::thread::spawn(move || serve_on(listener, Vec::new(), false));
+
+        let mut clients: Vec<_> = ["2025-06-18", "2024-11-05"]
+            .iter()
+            .map(|version| {
+                let mut stream = UnixStream::connect(&path).unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let initialize = format!(
+                    r#"{{"jsonrpc":"2.0","id":1,"method":"initialize","params":{{"protocolVersion":"{version}"}}}}"#
+                );
+                let response = request(&mut stream, &mut reader, &initialize);
+                assert_eq!(response["result"]["protocolVersion"], *version);
+                (stream, reader)
+            })
+            .collect();
+
+        // Each connection keeps the revision it negotiated
+        let list = r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#;
+        let (stream, reader) = &mut clients[0];
+        let tools = request(stream, reader, list);
+        assert!(tools["result"]["tools"][0].get("annotations").is_some());
+        let (stream, reader) = &mut clients[1];
+        let tools = request(stream, reader, list);
+        assert!(tools["result"]["tools"][0].get("annotations").is_none());
+    }
+
+    #[test]
+    fn test_bind_private() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("meta.sock");
+        let _listener = bind_private(&path).unwrap();
+
+        let metadata = std::fs::symlink_metadata(&path).unwrap();
+        assert!(metadata.file_type().is_socket());
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        assert!(UnixStream::connect(&path).is_ok());
+        // The staging directory is gone
+        assert_eq!(std::fs::read_dir(tmp.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_remove_stale_socket() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("meta.sock");
+
+        // Nothing there
+        remove_stale_socket(&path).unwrap();
+
+        // A live socket is left alone
+        let listener = UnixListener::bind(&path).unwrap();
+        assert!(remove_stale_socket(&path).is_err());
+
+        // Once nobody listens it is cleaned up
+        drop(listener);
+        remove_stale_socket(&path).unwrap();
+        assert!(!path.exists());
+
+        // Regular files are never removed
+        std::fs::write(&path, "data").unwrap();
+        assert!(remove_stale_socket(&path).is_err());
+        assert!(path.exists());
+    }
+}
@@ -0,0 +1,282 @@
+//! Resource subscriptions and `notifications/resources/updated`.
+//!
+//! A background watcher polls every subscribed resource and notifies the client
+//! when its fingerprint changes. Git state resources are fingerprinted from
+//! `RepoState` plus the HEAD commit, so new commits, dirty/clean transitions and
+//! branch switches are all picked up without the client re-polling
+//! `meta_workspace_state`.
+
+use super::resources::{PROJECT_PREFIX, STATE_SUFFIX, WORKSPACE_STATE_URI};
+use super::{McpServer, RpcError};
+use anyhow::Result;
+use meta_cli::query::RepoState;
+use meta_core::config::ProjectInfo;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often subscribed resources are re-checked.
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long each git command of a check may take, so one hung repository
+/// cannot hold up notifications about all the others.
+const WATCH_COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+impl McpServer {
+    pub(crate) fn handle_subscribe(&self, params: &serde_json::Value) -> Result<serde_json::Value> {
+        let uri = subscription_uri(params)?;
+        self.subscriptions
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Subscriptions lock poisoned"))?
+            .insert(uri.to_string());
+        Ok(serde_json::json!({}))
+    }
+
+    pub(crate) fn handle_unsubscribe(
+        &self,
+        params: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let uri = subscription_uri(params)?;
+        self.subscriptions
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Subscriptions lock poisoned"))?
+            .remove(uri);
+        Ok(serde_json::json!({}))
+    }
+
+    /// Start the background thread that polls subscribed resources and the
+    /// workspace tools. It stops once the returned flag is set.
+    pub(crate) fn spawn_watcher(&self) -> Arc<AtomicBool> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let server = self.clone();
+        let stopped = stop.clone();
+        std::thread::spawn(move || {
+            let mut fingerprints = HashMap::new();
+            let mut tool_list = None;
+            while !stopped.load(Ordering::SeqCst) {
+                std::thread::sleep(WATCH_INTERVAL);
+                let server = server.watcher_handle();
+                server.poll_subscriptions(&mut fingerprints);
+                server.refresh_stale_plugins();
+                server.poll_tool_list(&mut tool_list);
+            }
+        });
+        stop
+    }
+
+    /// The handle one round of the watcher works through: the current
+    /// workspace, and a time limit since no tool call sets one.
+    fn watcher_handle(&self) -> Self {
+        McpServer {
+            timeout: Some(WATCH_COMMAND_TIMEOUT),
+            ..self.with_current_workspace()
+        }
+    }
+
+    /// Re-fingerprint every subscribed resource and notify about the ones that
+    /// changed since the previous poll. A newly subscribed resource only records
+    /// its baseline.
+    fn poll_subscriptions(&self, fingerprints: &mut HashMap<String, Option<u64>>) {
+        let subscribed: BTreeSet<String> = match self.subscriptions.lock() {
+            Ok(subscriptions) => subscriptions.clone(),
+            Err(_) => return,
+        };
+        fingerprints.retain(|uri, _| subscribed.contains(uri));
+
+        for uri in subscribed {
+            let fingerprint = self.resource_fingerprint(&uri);
+            match fingerprints.insert(uri.clone(), fingerprint) {
+                Some(previous) if previous != fingerprint => {
+                    if let Err(e) = self.send_notification(
+                        "notifications/resources/updated",
+                        serde_json::json!({ "uri": uri }),
+                    ) {
+                        eprintln!("Failed to send resource update: {e}");
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Hash the current content of a resource, or `None` if it cannot be read.
+    fn resource_fingerprint(&self, uri: &str) -> Option<u64> {
+        let meta_dir = self.meta_dir.as_ref()?;
+        let mut hasher = DefaultHasher::new();
+
+        if uri == WORKSPACE_STATE_URI {
+            for project in self.load_projects(meta_dir).ok()? {
+                project.name.hash(&mut hasher);
+                self.git_fingerprint(meta_dir, &project).hash(&mut hasher);
+            }
+            return Some(hasher.finish());
+        }
+
+        let state_project = uri
+            .strip_prefix(PROJECT_PREFIX)
+            .and_then(|rest| rest.strip_suffix(STATE_SUFFIX))
+            .and_then(|name| {
+                self.load_projects(meta_dir)
+                    .ok()?
+                    .into_iter()
+                    .find(|p| p.name == name)
+            });
+        if let Some(project) = state_project {
+            self.git_fingerprint(meta_dir, &project)?.hash(&mut hasher);
+            return Some(hasher.finish());
+        }
+
+        let (_, text) = self.read_resource(uri).ok()?;
+        text.hash(&mut hasher);
+        Some(hasher.finish())
+    }
+
+    /// Serialized `RepoState` plus HEAD, which together change on new commits,
+    /// dirty/clean transitions and branch switches.
+    fn git_fingerprint(
+        &self,
+        meta_dir: &Path,
+        project: &ProjectInfo,
+    ) -> Option<(Option<String>, Option<String>)> {
+        let project_path = meta_dir.join(&project.path);
+        if !project_path.exists() {
+            return None;
+        }
+
+        let state = RepoState::collect(&project.name, &project_path, &project.tags)
+            .ok()
+            .and_then(|state| serde_json::to_string(&state).ok());
+        let head = self.git_output(&project_path, &["rev-parse", "HEAD"]).ok();
+        Some((state, head))
+    }
+}
+
+fn subscription_uri(params: &serde_json::Value) -> Result<&str> {
+    params
+        .get("uri")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::new(-32602, "Missing 'uri' parameter").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::SharedBuffer;
+    use crate::Outgoing;
+
+    fn server_with_buffer() -> (McpServer, SharedBuffer, tempfile::TempDir) {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            tmp.path().join(".meta"),
+            r#"{"projects": {"api": "git@github.com:example/api.git"}}"#,
+        )
+        .unwrap();
+        std::fs::create_dir_all(tmp.path().join("api")).unwrap();
+        std::fs::write(tmp.path().join("api/README.md"), "# API\n").unwrap();
+
+        let buffer = SharedBuffer::default();
+        let server = McpServer::with_meta_dir(
+            Some(tmp.path().to_path_buf()),
+            Outgoing::new(Box::new(buffer.clone())),
+        );
+        (server, buffer, tmp)
+    }
+
+    #[test]
+    fn test_subscribe_and_unsubscribe() {
+        let (server, _buffer, _tmp) = server_with_buffer();
+        let params = serde_json::json!({"uri": "meta://project/api/state"});
+
+        server.handle_subscribe(&params).unwrap();
+        assert!(server
+            .subscriptions
+            .lock()
+            .unwrap()
+            .contains("meta://project/api/state"));
+
+        server.handle_unsubscribe(&params).unwrap();
+        assert!(server.subscriptions.lock().unwrap().is_empty());
+
+        let err = server.handle_subscribe(&serde_json::json!({})).unwrap_err();
+        assert_eq!(err.downcast_ref::<RpcError>().unwrap().code, -32602);
+    }
+
+    #[test]
+    fn test_watcher_commands_are_time_limited() {
+        let (server, _buffer, _tmp) = server_with_buffer();
+        assert_eq!(server.timeout, None);
+
+        let watcher = server.watcher_handle();
+        assert_eq!(watcher.timeout, Some(WATCH_COMMAND_TIMEOUT));
+        assert_eq!(watcher.meta_dir, server.meta_dir);
+    }
+
+    #[test]
+    fn test_poll_notifies_only_on_change() {
+        let (server, buffer, tmp) = server_with_buffer();
+        server
+            .handle_subscribe(&serde_json::json!({"uri": "meta://project/api/readme"}))
+            .unwrap();
+
+        let mut fingerprints = HashMap::new();
+        server.poll_subscriptions(&mut fingerprints);
+        server.poll_subscriptions(&mut fingerprints);
+        assert!(buffer.messages().is_empty());
+
+        std::fs::write(tmp.path().join("api/README.md"), "# API v2\n").unwrap();
+        server.poll_subscriptions(&mut fingerprints);
+
+        let messages = buffer.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["method"], "notifications/resources/updated");
+        assert_eq!(messages[0]["params"]["uri"], "meta://project/api/readme");
+    }
+
+    #[test]
+    fn test_poll_notifies_on_git_state_change() {
+        let (server, buffer, tmp) = server_with_buffer();
+        let api = tmp.path().join("api");
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(args)
+                .current_dir(&api)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success());
+        };
+        git(&["init", "-q"]);
+        git(&["config", "user.name", "Test"]);
+        git(&["config", "user.email", "test@example.com"]);
+        git(&["add", "README.md"]);
+        git(&["commit", "-q", "-m", "Add README"]);
+        server
+            .handle_subscribe(&serde_json::json!({"uri": "meta://project/api/state"}))
+            .unwrap();
+
+        let mut fingerprints = HashMap::new();
+        server.poll_subscriptions(&mut fingerprints);
+        server.poll_subscriptions(&mut fingerprints);
+        assert!(buffer.messages().is_empty());
+
+        // Dirty worktree
+        std::fs::write(api.join("README.md"), "# API v2\n").unwrap();
+        server.poll_subscriptions(&mut fingerprints);
+        assert_eq!(buffer.messages().len(), 1);
+
+        // New commit, which also leaves the worktree clean again
+        git(&["commit", "-q", "-am", "Update README"]);
+        server.poll_subscriptions(&mut fingerprints);
+
+        let messages = buffer.messages();
+        assert_eq!(messages.len(), 2);
+        for message in &messages {
+            assert_eq!(message["method"], "notifications/resources/updated");
+            assert_eq!(message["params"]["uri"], "meta://project/api/state");
+        }
+    }
+}
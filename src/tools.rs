@@ -0,0 +1,1142 @@
+//! The tool registry.
+//!
+//! Every tool the server offers is a `ToolHandler` declaring its definition
+//! (name, schemas and annotations) together with the code that runs it. The
+//! registry is assembled for each `tools/list` and `tools/call`, from the
+//! built-in tools below plus the workspace scripts and installed plugins, so
+//! what is registered can depend on the workspace a call targets.
+//!
+//! Tools the workspace settings turn off are withheld: neither listed nor
+//! callable. So is, in read-only mode, every tool but the built-in ones
+//! annotated `readOnlyHint`. Script and plugin annotations come from the
+//! workspace or a third party, so they are not trusted to keep a session
+//! read-only; the settings can list such tools in `read_only_tools`, unless
+//! the server itself runs with `--read-only`.
+
+use super::executor::{command_result_schema, concurrency_schema};
+use super::process::add_timeout_argument;
+use super::workspaces::add_workspace_argument;
+use super::{results_schema, McpServer, Tool, ToolAnnotations, ToolOutput};
+use anyhow::Result;
+
+/// A tool: its definition for `tools/list` and its handler for `tools/call`.
+pub(crate) trait ToolHandler {
+    fn definition(&self) -> &Tool;
+
+    fn call(&self, server: &McpServer, args: &serde_json::Value) -> Result<ToolOutput>;
+}
+
+/// The tools available to one request, in the order they are listed.
+#[derive(Default)]
+pub(crate) struct ToolRegistry {
+    tools: Vec<RegisteredTool>,
+    /// Names of withheld tools, with why.
+    withheld: Vec<(String, &'static str)>,
+}
+
+/// A tool with its definition as advertised, including the `timeout_secs`
+/// argument every tool takes and the `workspace` argument every tool but
+/// `meta_list_workspaces` takes.
+struct RegisteredTool {
+    definition: Tool,
+    handler: Box<dyn ToolHandler>,
+    /// Whether it is one of the server's own tools, whose annotations hold.
+    builtin: bool,
+}
+
+impl ToolHandler for RegisteredTool {
+    fn definition(&self) -> &Tool {
+        &self.definition
+    }
+
+    fn call(&self, server: &McpServer, args: &serde_json::Value) -> Result<ToolOutput> {
+        self.handler.call(server, args)
+    }
+}
+
+impl ToolRegistry {
+    /// Add a script or plugin tool. A tool whose name is already taken is
+    /// ignored, so a plugin or script can never shadow a built-in tool.
+    pub(crate) fn register(&mut self, tool: impl ToolHandler + 'static) {
+        self.add(tool, false);
+    }
+
+    fn register_builtin(&mut self, tool: BuiltinTool) {
+        self.add(tool, true);
+    }
+
+    fn add(&mut self, tool: impl ToolHandler + 'static, builtin: bool) {
+        let mut definition = tool.definition().clone();
+        if self.get(&definition.name).is_some() {
+            return;
+        }
+        if definition.name != "meta_list_workspaces" {
+            add_workspace_argument(&mut definition.input_schema);
+        }
+        add_timeout_argument(&mut definition.input_schema);
+        self.tools.push(RegisteredTool {
+            definition,
+            handler: Box::new(tool),
+            builtin,
+        });
+    }
+
+    /// Withhold every tool `reason` gives a reason for.
+    fn withhold(&mut self, reason: impl Fn(&RegisteredTool) -> Option<&'static str>) {
+        let (withheld, kept) = std::mem::take(&mut self.tools)
+            .into_iter()
+            .partition::<Vec<_>, _>(|tool| reason(tool).is_some());
+        self.tools = kept;
+        for tool in withheld {
+            if let Some(reason) = reason(&tool) {
+                self.withheld.push((tool.definition.name, reason));
+            }
+        }
+    }
+
+    /// Why the tool called `name` is withheld, if it is.
+    pub(crate) fn withheld(&self, name: &str) -> Option<&'static str> {
+        self.withheld
+            .iter()
+            .find(|(withheld, _)| withheld == name)
+            .map(|(_, reason)| *reason)
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&dyn ToolHandler> {
+        self.tools
+            .iter()
+            .find(|tool| tool.definition.name == name)
+            .map(|tool| tool as &dyn ToolHandler)
+    }
+
+    /// Whether the tool called `name` is a built-in one that only reads.
+    /// Script and plugin annotations are not taken at their word.
+    pub(crate) fn is_read_only(&self, name: &str) -> bool {
+        self.tools.iter().any(|tool| {
+            tool.definition.name == name
+                && tool.builtin
+                && tool.definition.annotations.read_only_hint
+        })
+    }
+
+    pub(crate) fn definitions(&self) -> impl Iterator<Item = &Tool> {
+        self.tools.iter().map(|tool| &tool.definition)
+    }
+}
+
+impl McpServer {
+    /// The tools this handle offers: built-ins first, then the scripts of its
+    /// workspace, then the installed plugins, less any withheld.
+    pub(crate) fn tool_registry(&self) -> ToolRegistry {
+        let mut registry = ToolRegistry::default();
+        for tool in builtin_tools() {
+            registry.register_builtin(tool);
+        }
+        for tool in self.script_tools() {
+            registry.register(tool);
+        }
+        for tool in self.plugin_tools() {
+            registry.register(tool);
+        }
+        let config = self.server_config();
+        let read_only = self.read_only || config.read_only;
+        registry.withhold(|tool| {
+            let name = &tool.definition.name;
+            let trusted_read_only = tool.builtin && tool.definition.annotations.read_only_hint;
+            // The settings may let a script or plugin through, but they come
+            // with the workspace and so never loosen `--read-only`
+            let listed = !tool.builtin && !self.read_only && config.read_only_tool_listed(name);
+            if !config.tool_enabled(name) {
+                Some("is disabled in this workspace")
+            } else if read_only && !trusted_read_only && !listed {
+                Some("is not available in read-only mode")
+            } else {
+                None
+            }
+        });
+        registry
+    }
+}
+
+/// A tool implemented by a `tool_*` method of the server.
+struct BuiltinTool {
+    handler: fn(&McpServer, &serde_json::Value) -> Result<ToolOutput>,
+    definition: Tool,
+}
+
+impl ToolHandler for BuiltinTool {
+    fn definition(&self) -> &Tool {
+        &self.definition
+    }
+
+    fn call(&self, server: &McpServer, args: &serde_json::Value) -> Result<ToolOutput> {
+        (self.handler)(server, args)
+    }
+}
+
+fn builtin_tools() -> Vec<BuiltinTool> {
+    vec![
+        // ================================================================
+        // Core Tools
+        // ================================================================
+        BuiltinTool {
+            handler: McpServer::tool_list_projects,
+            definition: Tool {
+                name: "meta_list_projects".to_string(),
+                description: "List all projects in the meta repository".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag"
+                        }
+                    }
+                }),
+                output_schema: None,
+                annotations: ToolAnnotations::read_only(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_list_workspaces,
+            definition: Tool {
+                name: "meta_list_workspaces".to_string(),
+                description: "List the meta workspaces this server can work on; pass one as the 'workspace' argument of any other tool".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+                output_schema: Some(results_schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "path": { "type": "string" },
+                        "default": { "type": "boolean" },
+                        "source": { "type": "string", "enum": ["roots", "configured"] },
+                        "projects": { "type": ["integer", "null"] }
+                    },
+                    "required": ["name", "path", "default", "source"]
+                }))),
+                annotations: ToolAnnotations::read_only(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_exec,
+            definition: Tool {
+                name: "meta_exec".to_string(),
+                description: "Execute a command across all meta projects. The command is a program and its arguments, run directly in each project: there is no shell, and meta subcommands and plugins are not available (use the meta_git_* and meta_plugin_* tools)".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "command": {
+                            "type": "string",
+                            "description": "Program and arguments to run in each project, split on whitespace"
+                        },
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag"
+                        },
+                        "concurrency": concurrency_schema()
+                    },
+                    "required": ["command"]
+                }),
+                output_schema: Some(results_schema(command_result_schema())),
+                annotations: ToolAnnotations::destructive(false).open_world(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_get_config,
+            definition: Tool {
+                name: "meta_get_config".to_string(),
+                description: "Get the meta repository configuration including all projects, tags, and settings".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+                output_schema: None,
+                annotations: ToolAnnotations::read_only(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_get_project_path,
+            definition: Tool {
+                name: "meta_get_project_path".to_string(),
+                description: "Get the absolute path for a specific project".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "project": {
+                            "type": "string",
+                            "description": "Name of the project"
+                        }
+                    },
+                    "required": ["project"]
+                }),
+                output_schema: Some(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "project": { "type": "string" },
+                        "path": { "type": "string" },
+                        "exists": { "type": "boolean" }
+                    },
+                    "required": ["project", "path", "exists"]
+                })),
+                annotations: ToolAnnotations::read_only(),
+            },
+        },
+        // ================================================================
+        // Multi-Repo Git Tools (Phase 5.1)
+        // ================================================================
+        BuiltinTool {
+            handler: McpServer::tool_git_status,
+            definition: Tool {
+                name: "meta_git_status".to_string(),
+                description: "Get git status for all projects with structured output showing dirty/clean state, branch, and changes".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "project": {
+                            "type": "string",
+                            "description": "Specific project to check (optional, defaults to all)"
+                        },
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag"
+                        },
+                        "concurrency": concurrency_schema()
+                    }
+                }),
+                output_schema: Some(results_schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "project": { "type": "string" },
+                        "branch": { "type": ["string", "null"] },
+                        "dirty": { "type": "boolean" },
+                        "changes": {
+                            "type": "array",
+                            "items": { "type": "string" }
+                        },
+                        "error": { "type": "string" },
+                        "timed_out": { "type": "boolean" }
+                    },
+                    "required": ["project"]
+                }))),
+                annotations: ToolAnnotations::read_only(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_git_pull,
+            definition: Tool {
+                name: "meta_git_pull".to_string(),
+                description: "Pull changes from remote for all projects or filtered by tag".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag"
+                        },
+                        "rebase": {
+                            "type": "boolean",
+                            "description": "Use rebase instead of merge (default: false)"
+                        },
+                        "concurrency": concurrency_schema()
+                    }
+                }),
+                output_schema: Some(results_schema(command_result_schema())),
+                annotations: ToolAnnotations::additive(false).open_world(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_git_push,
+            definition: Tool {
+                name: "meta_git_push".to_string(),
+                description: "Push commits to remote for all projects or filtered by tag".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag"
+                        },
+                        "concurrency": concurrency_schema()
+                    }
+                }),
+                output_schema: Some(results_schema(command_result_schema())),
+                annotations: ToolAnnotations::additive(false).open_world(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_git_fetch,
+            definition: Tool {
+                name: "meta_git_fetch".to_string(),
+                description: "Fetch from remotes for all projects in parallel".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag"
+                        },
+                        "concurrency": concurrency_schema()
+                    }
+                }),
+                output_schema: Some(results_schema(command_result_schema())),
+                annotations: ToolAnnotations::additive(true).open_world(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_git_diff,
+            definition: Tool {
+                name: "meta_git_diff".to_string(),
+                description: "Get diffs across repositories showing what has changed".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "project": {
+                            "type": "string",
+                            "description": "Specific project to diff (optional, defaults to all)"
+                        },
+                        "staged": {
+                            "type": "boolean",
+                            "description": "Show only staged changes (default: false shows unstaged)"
+                        },
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag"
+                        },
+                        "concurrency": concurrency_schema()
+                    }
+                }),
+                output_schema: Some(results_schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "project": { "type": "string" },
+                        "diff": { "type": "string" },
+                        "error": { "type": "string" },
+                        "timed_out": { "type": "boolean" }
+                    },
+                    "required": ["project"]
+                }))),
+                annotations: ToolAnnotations::read_only(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_git_branch,
+            definition: Tool {
+                name: "meta_git_branch".to_string(),
+                description: "Get branch information for all projects including current branch, tracking branch, and ahead/behind status".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag"
+                        },
+                        "concurrency": concurrency_schema()
+                    }
+                }),
+                output_schema: Some(results_schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "project": { "type": "string" },
+                        "branch": { "type": "string" },
+                        "tracking": { "type": ["string", "null"] },
+                        "ahead": { "type": "integer" },
+                        "behind": { "type": "integer" },
+                        "error": { "type": "string" },
+                        "timed_out": { "type": "boolean" }
+                    },
+                    "required": ["project"]
+                }))),
+                annotations: ToolAnnotations::read_only(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_git_add,
+            definition: Tool {
+                name: "meta_git_add".to_string(),
+                description: "Stage files across repositories".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "project": {
+                            "type": "string",
+                            "description": "Specific project to add files in (optional, defaults to all)"
+                        },
+                        "files": {
+                            "type": "string",
+                            "description": "Files to add (default: '.' for all changed files)"
+                        },
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag"
+                        },
+                        "concurrency": concurrency_schema()
+                    }
+                }),
+                output_schema: Some(results_schema(command_result_schema())),
+                annotations: ToolAnnotations::additive(true),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_git_commit,
+            definition: Tool {
+                name: "meta_git_commit".to_string(),
+                description: "Commit staged changes across repositories with a shared message".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "message": {
+                            "type": "string",
+                            "description": "Commit message"
+                        },
+                        "project": {
+                            "type": "string",
+                            "description": "Specific project to commit in (optional, defaults to all with staged changes)"
+                        },
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag"
+                        },
+                        "concurrency": concurrency_schema()
+                    },
+                    "required": ["message"]
+                }),
+                output_schema: Some(results_schema(command_result_schema())),
+                annotations: ToolAnnotations::additive(false),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_git_checkout,
+            definition: Tool {
+                name: "meta_git_checkout".to_string(),
+                description: "Checkout a branch across repositories".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "branch": {
+                            "type": "string",
+                            "description": "Branch name to checkout"
+                        },
+                        "create": {
+                            "type": "boolean",
+                            "description": "Create the branch if it doesn't exist (default: false)"
+                        },
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag"
+                        },
+                        "concurrency": concurrency_schema()
+                    },
+                    "required": ["branch"]
+                }),
+                output_schema: Some(results_schema(command_result_schema())),
+                annotations: ToolAnnotations::additive(true),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_git_multi_commit,
+            definition: Tool {
+                name: "meta_git_multi_commit".to_string(),
+                description: "Create commits with different messages for each repository. Allows tailored commit messages per project.".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "commits": {
+                            "type": "array",
+                            "description": "Array of commit objects, each specifying a project and message",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "project": {
+                                        "type": "string",
+                                        "description": "Project name (use '.' for root repo)"
+                                    },
+                                    "message": {
+                                        "type": "string",
+                                        "description": "Commit message for this project"
+                                    }
+                                },
+                                "required": ["project", "message"]
+                            }
+                        }
+                    },
+                    "required": ["commits"]
+                }),
+                output_schema: Some(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "results": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "project": { "type": "string" },
+                                    "success": { "type": "boolean" },
+                                    "message": { "type": "string" },
+                                    "error": { "type": "string" }
+                                },
+                                "required": ["project", "success", "message"]
+                            }
+                        },
+                        "summary": {
+                            "type": "object",
+                            "properties": {
+                                "total": { "type": "integer" },
+                                "succeeded": { "type": "integer" },
+                                "failed": { "type": "integer" }
+                            },
+                            "required": ["total", "succeeded", "failed"]
+                        }
+                    },
+                    "required": ["results", "summary"]
+                })),
+                annotations: ToolAnnotations::additive(false),
+            },
+        },
+        // ================================================================
+        // Build/Test Orchestration Tools (Phase 5.2)
+        // ================================================================
+        BuiltinTool {
+            handler: McpServer::tool_detect_build_systems,
+            definition: Tool {
+                name: "meta_detect_build_systems".to_string(),
+                description: "Detect build systems (Cargo, npm, make, etc.) for each project".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag"
+                        }
+                    }
+                }),
+                output_schema: Some(results_schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "project": { "type": "string" },
+                        "path": { "type": "string" },
+                        "build_systems": {
+                            "type": "array",
+                            "items": { "type": "string" }
+                        }
+                    },
+                    "required": ["project", "path", "build_systems"]
+                }))),
+                annotations: ToolAnnotations::read_only(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_run_tests,
+            definition: Tool {
+                name: "meta_run_tests".to_string(),
+                description: "Run tests across all projects using detected build systems".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag"
+                        },
+                        "project": {
+                            "type": "string",
+                            "description": "Specific project to test"
+                        },
+                        "concurrency": concurrency_schema()
+                    }
+                }),
+                output_schema: Some(results_schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "project": { "type": "string" },
+                        "command": { "type": "string" },
+                        "success": { "type": "boolean" },
+                        "stdout": { "type": "string" },
+                        "stderr": { "type": "string" },
+                        "error": { "type": "string" },
+                        "timed_out": { "type": "boolean" }
+                    },
+                    "required": ["project"]
+                }))),
+                annotations: ToolAnnotations::additive(true).open_world(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_build,
+            definition: Tool {
+                name: "meta_build".to_string(),
+                description: "Build all projects with cargo build, or the build command the workspace configures".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag"
+                        },
+                        "release": {
+                            "type": "boolean",
+                            "description": "Build in release mode (default: false). Does not apply to projects whose build command the workspace configures; those run as configured"
+                        },
+                        "concurrency": concurrency_schema()
+                    }
+                }),
+                output_schema: Some(results_schema(command_result_schema())),
+                annotations: ToolAnnotations::additive(true).open_world(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_clean,
+            definition: Tool {
+                name: "meta_clean".to_string(),
+                description: "Clean build artifacts across all projects".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag"
+                        },
+                        "concurrency": concurrency_schema()
+                    }
+                }),
+                output_schema: Some(results_schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "project": { "type": "string" },
+                        "command": { "type": "string" },
+                        "success": { "type": "boolean" },
+                        "error": { "type": "string" },
+                        "timed_out": { "type": "boolean" }
+                    },
+                    "required": ["project"]
+                }))),
+                annotations: ToolAnnotations::destructive(true),
+            },
+        },
+        // ================================================================
+        // Project Discovery & Analysis Tools (Phase 5.3)
+        // ================================================================
+        BuiltinTool {
+            handler: McpServer::tool_search_code,
+            definition: Tool {
+                name: "meta_search_code".to_string(),
+                description: "Search for patterns across all repositories using grep".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "pattern": {
+                            "type": "string",
+                            "description": "Search pattern (regex supported)"
+                        },
+                        "file_pattern": {
+                            "type": "string",
+                            "description": "File glob pattern to filter (e.g., '*.rs', '*.ts')"
+                        },
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag"
+                        },
+                        "concurrency": concurrency_schema()
+                    },
+                    "required": ["pattern"]
+                }),
+                output_schema: Some(results_schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "project": { "type": "string" },
+                        "matches": {
+                            "type": "array",
+                            "items": { "type": "string" }
+                        },
+                        "error": { "type": "string" },
+                        "timed_out": { "type": "boolean" }
+                    },
+                    "required": ["project"]
+                }))),
+                annotations: ToolAnnotations::read_only(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_get_file_tree,
+            definition: Tool {
+                name: "meta_get_file_tree".to_string(),
+                description: "Get file tree structure for projects".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "project": {
+                            "type": "string",
+                            "description": "Specific project to get tree for (optional, defaults to all)"
+                        },
+                        "depth": {
+                            "type": "integer",
+                            "description": "Maximum depth to traverse (default: 3)"
+                        },
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag"
+                        }
+                    }
+                }),
+                output_schema: Some(results_schema(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "project": { "type": "string" },
+                        "tree": { "type": ["array", "null"] }
+                    },
+                    "required": ["project", "tree"]
+                }))),
+                annotations: ToolAnnotations::read_only(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_list_plugins,
+            definition: Tool {
+                name: "meta_list_plugins".to_string(),
+                description: "List all installed meta plugins and their commands".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+                output_schema: None,
+                annotations: ToolAnnotations::read_only(),
+            },
+        },
+        // ================================================================
+        // AI-Dominance Tools (Phase 9)
+        // ================================================================
+        BuiltinTool {
+            handler: McpServer::tool_query_repos,
+            definition: Tool {
+                name: "meta_query_repos".to_string(),
+                description: "Query repositories by state/criteria using a simple DSL. Examples: 'dirty:true', 'tag:backend', 'dirty:true AND branch:main', 'modified_in:24h'".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {
+                            "type": "string",
+                            "description": "Query string using DSL (e.g., 'dirty:true AND tag:backend')"
+                        }
+                    },
+                    "required": ["query"]
+                }),
+                output_schema: Some(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "query": { "type": "string" },
+                        "matches": { "type": "integer" },
+                        "projects": {
+                            "type": "array",
+                            "items": { "type": "object" }
+                        },
+                        "failed": {
+                            "type": "array",
+                            "items": { "type": "object" }
+                        }
+                    },
+                    "required": ["query", "matches", "projects"]
+                })),
+                annotations: ToolAnnotations::read_only(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_workspace_state,
+            definition: Tool {
+                name: "meta_workspace_state".to_string(),
+                description: "Get a summary of the entire workspace state including dirty/clean counts, branches, and tags".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "concurrency": concurrency_schema()
+                    }
+                }),
+                output_schema: Some(serde_json::json!({ "type": "object" })),
+                annotations: ToolAnnotations::read_only(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_analyze_impact,
+            definition: Tool {
+                name: "meta_analyze_impact".to_string(),
+                description: "Analyze what would be affected if a project changes. Returns direct and transitive dependents.".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "project": {
+                            "type": "string",
+                            "description": "Project name to analyze impact for"
+                        }
+                    },
+                    "required": ["project"]
+                }),
+                output_schema: Some(serde_json::json!({ "type": "object" })),
+                annotations: ToolAnnotations::read_only(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_execution_order,
+            definition: Tool {
+                name: "meta_execution_order".to_string(),
+                description: "Get topological execution order respecting dependencies. Dependencies come before dependents.".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter by tag (optional)"
+                        }
+                    }
+                }),
+                output_schema: Some(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "execution_order": {
+                            "type": "array",
+                            "items": { "type": "string" }
+                        },
+                        "count": { "type": "integer" },
+                        "tag_filter": { "type": ["string", "null"] }
+                    },
+                    "required": ["execution_order", "count"]
+                })),
+                annotations: ToolAnnotations::read_only(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_snapshot_create,
+            definition: Tool {
+                name: "meta_snapshot_create".to_string(),
+                description: "Create a snapshot of the current workspace state for later rollback".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                            "description": "Name for the snapshot"
+                        },
+                        "description": {
+                            "type": "string",
+                            "description": "Optional description"
+                        }
+                    },
+                    "required": ["name"]
+                }),
+                output_schema: Some(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "status": { "type": "string" },
+                        "name": { "type": "string" },
+                        "path": { "type": "string" },
+                        "projects_count": { "type": "integer" }
+                    },
+                    "required": ["status", "name", "path", "projects_count"]
+                })),
+                annotations: ToolAnnotations::additive(false),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_snapshot_list,
+            definition: Tool {
+                name: "meta_snapshot_list".to_string(),
+                description: "List all available workspace snapshots".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+                output_schema: Some(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "snapshots": {
+                            "type": "array",
+                            "items": { "type": "object" }
+                        },
+                        "count": { "type": "integer" }
+                    },
+                    "required": ["snapshots", "count"]
+                })),
+                annotations: ToolAnnotations::read_only(),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_snapshot_restore,
+            definition: Tool {
+                name: "meta_snapshot_restore".to_string(),
+                description: "Restore workspace to a previously saved snapshot".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                            "description": "Name of the snapshot to restore"
+                        },
+                        "force": {
+                            "type": "boolean",
+                            "description": "Force restore even if there are uncommitted changes (default: false)"
+                        }
+                    },
+                    "required": ["name"]
+                }),
+                output_schema: Some(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "status": {
+                            "type": "string",
+                            "enum": ["success", "partial"]
+                        },
+                        "restored": {
+                            "type": "array",
+                            "items": { "type": "string" }
+                        },
+                        "failed": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "project": { "type": "string" },
+                                    "error": { "type": "string" }
+                                },
+                                "required": ["project", "error"]
+                            }
+                        },
+                        "restored_count": { "type": "integer" },
+                        "failed_count": { "type": "integer" }
+                    },
+                    "required": ["status", "restored", "failed", "restored_count", "failed_count"]
+                })),
+                annotations: ToolAnnotations::destructive(true),
+            },
+        },
+        BuiltinTool {
+            handler: McpServer::tool_batch_execute,
+            definition: Tool {
+                name: "meta_batch_execute".to_string(),
+                description: "Execute a command across projects with optional atomic rollback on failure".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "command": {
+                            "type": "string",
+                            "description": "Command to execute in each project"
+                        },
+                        "tag": {
+                            "type": "string",
+                            "description": "Filter projects by tag (optional)"
+                        },
+                        "atomic": {
+                            "type": "boolean",
+                            "description": "If true, automatically rollback all projects if any fail (default: false)"
+                        },
+                        "concurrency": concurrency_schema()
+                    },
+                    "required": ["command"]
+                }),
+                output_schema: Some(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "command": { "type": "string" },
+                        "results": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "project": { "type": "string" },
+                                    "success": { "type": "boolean" },
+                                    "stdout": { "type": "string" },
+                                    "stderr": { "type": "string" },
+                                    "error": { "type": "string" },
+                                    "timed_out": { "type": "boolean" }
+                                },
+                                "required": ["project", "success"]
+                            }
+                        },
+                        "has_failure": { "type": "boolean" },
+                        "rolled_back": { "type": "boolean" },
+                        "rollback_result": { "type": ["object", "null"] }
+                    },
+                    "required": ["command", "results", "has_failure", "rolled_back"]
+                })),
+                annotations: ToolAnnotations::destructive(false).open_world(),
+            },
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(name: &str) -> BuiltinTool {
+        BuiltinTool {
+            handler: |_, _| Ok(ToolOutput::Text("called".to_string())),
+            definition: Tool {
+                name: name.to_string(),
+                description: String::new(),
+                input_schema: serde_json::json!({"type": "object", "properties": {}}),
+                output_schema: None,
+                annotations: ToolAnnotations::read_only(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_registry_keeps_first_tool_with_a_name() {
+        let mut registry = ToolRegistry::default();
+        registry.register(tool("meta_a"));
+        registry.register(BuiltinTool {
+            handler: |_, _| anyhow::bail!("shadowed"),
+            ..tool("meta_a")
+        });
+        registry.register(tool("meta_b"));
+
+        let names: Vec<&str> = registry.definitions().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["meta_a", "meta_b"]);
+
+        let server =
+            McpServer::with_meta_dir(None, crate::Outgoing::new(Box::new(std::io::sink())));
+        let output = registry
+            .get("meta_a")
+            .unwrap()
+            .call(&server, &serde_json::json!({}))
+            .unwrap();
+        assert_eq!(output.to_string(), "called");
+        assert!(registry.get("meta_c").is_none());
+    }
+
+    #[test]
+    fn test_workspace_settings_turn_tools_off() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            tmp.path().join(".meta"),
+            r#"{"projects": {}, "mcp": {"disabled_tools": ["meta_git_*", "meta_build"]}}"#,
+        )
+        .unwrap();
+        let server = McpServer::with_meta_dir(
+            Some(tmp.path().to_path_buf()),
+            crate::Outgoing::new(Box::new(std::io::sink())),
+        );
+        server.plugins.set(Vec::new());
+
+        let registry = server.tool_registry();
+        assert!(registry.get("meta_git_push").is_none());
+        assert!(registry.get("meta_build").is_none());
+        assert!(registry.get("meta_run_tests").is_some());
+        let err = server
+            .call_tool("meta_build", &serde_json::json!({}))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Tool 'meta_build' is disabled in this workspace"
+        );
+        assert!(err.is::<crate::RpcError>());
+    }
+
+    #[test]
+    fn test_builtin_tool_names_are_unique() {
+        let tools = builtin_tools();
+        let mut names: Vec<&str> = tools.iter().map(|t| t.definition.name.as_str()).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), tools.len());
+    }
+}
@@ -0,0 +1,224 @@
+//! Checking tool arguments against the tool's `inputSchema`.
+//!
+//! Handlers read their arguments leniently and fall back to defaults, so a
+//! mistyped value (`"depth": "5"`) or a misspelled key would otherwise be
+//! ignored without a word. Calls are checked before dispatch instead, and
+//! every violation is reported back as `-32602 Invalid params`.
+//!
+//! Only the JSON Schema keywords the tool schemas use are understood: `type`
+//! (a single type or a list), `properties`, `required`, `items`, `enum`,
+//! `minimum` and `additionalProperties`. Keys missing from `properties` are rejected unless
+//! `additionalProperties` allows them.
+
+use serde_json::Value;
+
+/// Every way `value` fails to match `schema`, as readable messages.
+pub(crate) fn violations(schema: &Value, value: &Value) -> Vec<String> {
+    let mut violations = Vec::new();
+    check(schema, value, "", &mut violations);
+    violations
+}
+
+fn check(schema: &Value, value: &Value, path: &str, violations: &mut Vec<String>) {
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(|t| t.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| has_type(value, t)) {
+            violations.push(format!(
+                "{} must be {}, got {}",
+                describe(path),
+                types.join(" or "),
+                type_name(value)
+            ));
+            // Nested checks would only repeat the same mistake
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !allowed.contains(value) {
+            let allowed: Vec<String> = allowed.iter().map(|v| v.to_string()).collect();
+            violations.push(format!(
+                "{} must be one of {}, got {value}",
+                describe(path),
+                allowed.join(", ")
+            ));
+        }
+    }
+
+    if let (Some(number), Some(minimum)) = (
+        value.as_f64(),
+        schema.get("minimum").and_then(|m| m.as_f64()),
+    ) {
+        if number < minimum {
+            violations.push(format!(
+                "{} must be at least {}, got {value}",
+                describe(path),
+                schema["minimum"]
+            ));
+        }
+    }
+
+    if let Value::Object(object) = value {
+        for name in schema
+            .get("required")
+            .and_then(|r| r.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|r| r.as_str())
+        {
+            if !object.contains_key(name) {
+                violations.push(format!("missing required argument '{}'", join(path, name)));
+            }
+        }
+
+        let properties = schema.get("properties").and_then(|p| p.as_object());
+        let additional = schema.get("additionalProperties");
+        for (name, field) in object {
+            match (properties.and_then(|p| p.get(name)), additional) {
+                (Some(field_schema), _) => {
+                    check(field_schema, field, &join(path, name), violations)
+                }
+                (None, Some(Value::Bool(true))) => {}
+                (None, Some(extra @ Value::Object(_))) => {
+                    check(extra, field, &join(path, name), violations)
+                }
+                (None, _) if properties.is_some() => {
+                    violations.push(format!("unknown argument '{}'", join(path, name)))
+                }
+                (None, _) => {}
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            check(item_schema, item, &format!("{path}[{i}]"), violations);
+        }
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        // Handlers read integers with `as_i64`, which refuses 5.0
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        // Unknown types are not ours to reject
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{path}.{name}")
+    }
+}
+
+fn describe(path: &str) -> String {
+    if path.is_empty() {
+        "arguments".to_string()
+    } else {
+        format!("'{path}'")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "depth": { "type": "integer", "minimum": 1 },
+                "project": { "type": "string" },
+                "mode": { "type": "string", "enum": ["fast", "full"] },
+                "commits": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "project": { "type": "string" },
+                            "message": { "type": "string" }
+                        },
+                        "required": ["project", "message"]
+                    }
+                }
+            },
+            "required": ["project"]
+        })
+    }
+
+    #[test]
+    fn test_valid_arguments() {
+        let args = json!({
+            "project": "api",
+            "depth": 5,
+            "mode": "fast",
+            "commits": [{"project": "api", "message": "Fix"}]
+        });
+        assert!(violations(&schema(), &args).is_empty());
+    }
+
+    #[test]
+    fn test_every_violation_is_reported() {
+        let args = json!({
+            "depth": "5",
+            "mode": "slow",
+            "commits": [{"project": "api"}, {"project": 1, "message": "x", "extra": true}],
+            "dpeth": 3
+        });
+        assert_eq!(
+            violations(&schema(), &args),
+            [
+                "missing required argument 'project'",
+                "missing required argument 'commits[0].message'",
+                "unknown argument 'commits[1].extra'",
+                "'commits[1].project' must be string, got integer",
+                "'depth' must be integer, got string",
+                "unknown argument 'dpeth'",
+                "'mode' must be one of \"fast\", \"full\", got \"slow\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_minimum() {
+        assert_eq!(
+            violations(&schema(), &json!({"project": "api", "depth": 0})),
+            ["'depth' must be at least 1, got 0"]
+        );
+        assert!(violations(&schema(), &json!({"project": "api", "depth": 1})).is_empty());
+    }
+
+    #[test]
+    fn test_arguments_must_be_an_object() {
+        assert_eq!(
+            violations(&schema(), &json!(["api"])),
+            ["arguments must be object, got array"]
+        );
+    }
+}
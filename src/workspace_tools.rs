@@ -0,0 +1,197 @@
+//! Tools defined by the workspace rather than by this server.
+//!
+//! Scripts from the workspace settings (`scripts`, see `server_config`) and
+//! installed meta plugins (see `plugins`) are offered as tools of their own next
+//! to the built-in ones. Both change while the server runs, so the watcher
+//! re-checks them and sends `notifications/tools/list_changed` when the list
+//! differs.
+
+use super::executor::concurrency_schema;
+use super::tools::ToolHandler;
+use super::{McpServer, Tool, ToolAnnotations, ToolOutput};
+use anyhow::Result;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+const SCRIPT_PREFIX: &str = "meta_script_";
+
+/// A workspace script offered as a tool. It runs like `meta_batch_execute`
+/// with the script's command, taking the same arguments otherwise.
+pub(crate) struct ScriptTool {
+    definition: Tool,
+    command: String,
+}
+
+impl ToolHandler for ScriptTool {
+    fn definition(&self) -> &Tool {
+        &self.definition
+    }
+
+    fn call(&self, server: &McpServer, args: &serde_json::Value) -> Result<ToolOutput> {
+        let mut batch = serde_json::json!({ "command": self.command });
+        for name in ["tag", "atomic", "concurrency"] {
+            if let Some(value) = args.get(name) {
+                batch[name] = value.clone();
+            }
+        }
+        server.tool_batch_execute(&batch)
+    }
+}
+
+impl McpServer {
+    /// A tool for each script of this handle's workspace.
+    pub(crate) fn script_tools(&self) -> Vec<ScriptTool> {
+        if self.meta_dir.is_none() {
+            return Vec::new();
+        }
+        self.server_config()
+            .scripts
+            .iter()
+            .map(|(name, script)| ScriptTool {
+                definition: Tool {
+                    name: tool_name(SCRIPT_PREFIX, name),
+                    description: script.description().map_or_else(
+                        || {
+                            format!(
+                                "Run the workspace script '{name}' in every project: {}",
+                                script.command()
+                            )
+                        },
+                        str::to_string,
+                    ),
+                    input_schema: serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "tag": {
+                                "type": "string",
+                                "description": "Only run in projects with this tag"
+                            },
+                            "atomic": {
+                                "type": "boolean",
+                                "description": "If true, roll back all projects if any fail (default: false)"
+                            },
+                            "concurrency": concurrency_schema()
+                        }
+                    }),
+                    output_schema: None,
+                    annotations: ToolAnnotations::destructive(false).open_world(),
+                },
+                command: script.command().to_string(),
+            })
+            .collect()
+    }
+
+    /// Notify the client when the registered tools differ from the previous
+    /// poll. The first poll only records the baseline.
+    pub(crate) fn poll_tool_list(&self, fingerprint: &mut Option<u64>) {
+        let mut hasher = DefaultHasher::new();
+        for tool in self.tool_registry().definitions() {
+            serde_json::to_string(&tool)
+                .unwrap_or_default()
+                .hash(&mut hasher);
+        }
+        let current = hasher.finish();
+
+        if fingerprint
+            .replace(current)
+            .is_some_and(|previous| previous != current)
+        {
+            if let Err(e) =
+                self.send_notification("notifications/tools/list_changed", serde_json::json!({}))
+            {
+                eprintln!("Failed to send tool list change: {e}");
+            }
+        }
+    }
+}
+
+/// Tool names may only contain letters, digits, `_` and `-`.
+pub(crate) fn tool_name(prefix: &str, name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{prefix}{name}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::SharedBuffer;
+    use crate::Outgoing;
+
+    #[test]
+    fn test_script_tools() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join(".meta"), r#"{"projects": {}}"#).unwrap();
+        std::fs::write(
+            tmp.path().join(".meta-mcp.yaml"),
+            "scripts:\n  lint: npm run lint\n  audit: { command: npm audit, description: Audit dependencies }\n",
+        )
+        .unwrap();
+        let server = McpServer::with_meta_dir(
+            Some(tmp.path().to_path_buf()),
+            Outgoing::new(Box::new(std::io::sink())),
+        );
+
+        let tools = server.script_tools();
+        let definitions: Vec<(&str, &str)> = tools
+            .iter()
+            .map(|t| {
+                (
+                    t.definition.name.as_str(),
+                    t.definition.description.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            definitions,
+            [
+                ("meta_script_audit", "Audit dependencies"),
+                (
+                    "meta_script_lint",
+                    "Run the workspace script 'lint' in every project: npm run lint"
+                ),
+            ]
+        );
+        assert_eq!(tools[1].command, "npm run lint");
+    }
+
+    #[test]
+    fn test_tool_list_changes_with_scripts() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join(".meta"), r#"{"projects": {}}"#).unwrap();
+        let buffer = SharedBuffer::default();
+        let server = McpServer::with_meta_dir(
+            Some(tmp.path().to_path_buf()),
+            Outgoing::new(Box::new(buffer.clone())),
+        );
+        // Keep the test independent of any meta binary on PATH
+        server.plugins.set(Vec::new());
+
+        let mut fingerprint = None;
+        server.poll_tool_list(&mut fingerprint);
+        server.poll_tool_list(&mut fingerprint);
+        assert!(buffer.messages().is_empty());
+
+        std::fs::write(
+            tmp.path().join(".meta-mcp.yaml"),
+            "scripts:\n  lint: echo ok\n",
+        )
+        .unwrap();
+        server.poll_tool_list(&mut fingerprint);
+        let messages = buffer.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["method"], "notifications/tools/list_changed");
+
+        let registry = server.tool_registry();
+        assert!(registry.get("meta_script_lint").is_some());
+        assert!(registry.get("meta_script_unknown").is_none());
+    }
+}
@@ -0,0 +1,245 @@
+//! Serving several meta workspaces from one server.
+//!
+//! Workspaces come from `--workspace` (or the launch directory) and from the
+//! client's roots. Every tool takes an optional `workspace` argument naming one
+//! of them; without it the default workspace, the first one listed by
+//! `meta_list_workspaces`, is used.
+
+use super::{find_meta_dir, find_meta_dir_from, McpServer, RpcError, ToolOutput};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// The workspaces a server knows about.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Workspaces {
+    /// Given on the command line, or found from the launch directory.
+    configured: Vec<PathBuf>,
+    /// Found in the client's roots. These come first, so the default follows
+    /// what the client has open.
+    from_roots: Vec<PathBuf>,
+}
+
+impl Workspaces {
+    pub(crate) fn new(configured: Vec<PathBuf>) -> Self {
+        Self {
+            configured,
+            from_roots: Vec::new(),
+        }
+    }
+
+    pub(crate) fn set_from_roots(&mut self, from_roots: Vec<PathBuf>) {
+        self.from_roots = from_roots;
+    }
+
+    /// Every workspace, default first, without duplicates.
+    pub(crate) fn all(&self) -> Vec<PathBuf> {
+        let mut all: Vec<PathBuf> = Vec::new();
+        for dir in self.from_roots.iter().chain(&self.configured) {
+            if !all.contains(dir) {
+                all.push(dir.clone());
+            }
+        }
+        all
+    }
+
+    pub(crate) fn default_dir(&self) -> Option<PathBuf> {
+        self.all().into_iter().next()
+    }
+
+    /// Find a workspace by name or by a path inside it.
+    fn find(&self, workspace: &str) -> Result<PathBuf> {
+        let all = self.all();
+        if let Some(dir) = find_meta_dir_from(Path::new(workspace)).filter(|d| all.contains(d)) {
+            return Ok(dir);
+        }
+
+        let matches: Vec<&PathBuf> = all
+            .iter()
+            .filter(|d| workspace_name(d) == workspace)
+            .collect();
+        match matches.as_slice() {
+            [dir] => Ok((*dir).clone()),
+            [] => Err(anyhow::anyhow!(
+                "Unknown workspace '{workspace}'. Known workspaces: {}",
+                all.iter()
+                    .map(|d| workspace_name(d))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            _ => Err(anyhow::anyhow!(
+                "Workspace name '{workspace}' is ambiguous; use one of these paths: {}",
+                matches
+                    .iter()
+                    .map(|d| d.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+/// Resolve the directories given with `--workspace` to their meta workspaces,
+/// or fall back to the one containing the current directory.
+pub(crate) fn configured_workspaces(dirs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    if dirs.is_empty() {
+        return Ok(find_meta_dir().into_iter().collect());
+    }
+    dirs.iter()
+        .map(|dir| {
+            find_meta_dir_from(dir)
+                .ok_or_else(|| anyhow::anyhow!("No meta workspace found at {}", dir.display()))
+        })
+        .collect()
+}
+
+/// Name a workspace is addressed by: the name of its directory.
+fn workspace_name(dir: &Path) -> String {
+    dir.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| dir.display().to_string())
+}
+
+impl McpServer {
+    /// Handle for a tool call, on the workspace its `workspace` argument names.
+    /// An unknown workspace is an invalid parameter.
+    pub(crate) fn for_workspace_argument(&self, args: &serde_json::Value) -> Result<Self> {
+        let workspace = match args.get("workspace").and_then(|v| v.as_str()) {
+            Some(workspace) => workspace,
+            None => return Ok(self.clone()),
+        };
+        let meta_dir = self
+            .workspaces
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Workspaces lock poisoned"))?
+            .find(workspace)
+            .map_err(|e| RpcError::new(-32602, e.to_string()))?;
+
+        Ok(Self {
+            meta_dir: Some(meta_dir),
+            ..self.clone()
+        })
+    }
+
+    pub(crate) fn tool_list_workspaces(&self, _args: &serde_json::Value) -> Result<ToolOutput> {
+        let workspaces = self
+            .workspaces
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Workspaces lock poisoned"))?
+            .clone();
+
+        let results: Vec<serde_json::Value> = workspaces
+            .all()
+            .iter()
+            .enumerate()
+            .map(|(i, dir)| {
+                serde_json::json!({
+                    "name": workspace_name(dir),
+                    "path": dir,
+                    "default": i == 0,
+                    "source": if workspaces.from_roots.contains(dir) { "roots" } else { "configured" },
+                    "projects": self.load_projects(dir).map(|p| p.len()).ok(),
+                })
+            })
+            .collect();
+
+        ToolOutput::json(&results)
+    }
+}
+
+/// Add the optional `workspace` argument to a tool's input schema.
+pub(crate) fn add_workspace_argument(input_schema: &mut serde_json::Value) {
+    if let Some(properties) = input_schema
+        .get_mut("properties")
+        .and_then(|p| p.as_object_mut())
+    {
+        properties.insert(
+            "workspace".to_string(),
+            serde_json::json!({
+                "type": "string",
+                "description": "Workspace to use, by name or path (see meta_list_workspaces; default: the first one)"
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Outgoing;
+
+    fn workspace(root: &Path, name: &str, projects: &str) -> PathBuf {
+        let dir = root.join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(".meta"), format!(r#"{{"projects": {projects}}}"#)).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_roots_come_first_without_duplicates() {
+        let mut workspaces = Workspaces::new(vec!["/a".into(), "/b".into()]);
+        workspaces.set_from_roots(vec!["/b".into(), "/c".into()]);
+
+        assert_eq!(
+            workspaces.all(),
+            [
+                PathBuf::from("/b"),
+                PathBuf::from("/c"),
+                PathBuf::from("/a")
+            ]
+        );
+        assert_eq!(workspaces.default_dir(), Some(PathBuf::from("/b")));
+    }
+
+    #[test]
+    fn test_workspace_argument_selects_workspace() {
+        let tmp = tempfile::tempdir().unwrap();
+        let platform = workspace(tmp.path(), "platform", r#"{"infra": "x"}"#);
+        let product = workspace(tmp.path(), "product", r#"{"app": "y", "web": "z"}"#);
+        let server = McpServer::with_workspaces(
+            vec![platform.clone(), product.clone()],
+            Outgoing::new(Box::new(std::io::sink())),
+        );
+
+        let by_name = server
+            .for_workspace_argument(&serde_json::json!({"workspace": "product"}))
+            .unwrap();
+        assert_eq!(by_name.meta_dir, Some(product.clone()));
+
+        let by_path = server
+            .for_workspace_argument(&serde_json::json!({"workspace": product.join("app")}))
+            .unwrap();
+        assert_eq!(by_path.meta_dir, Some(product));
+
+        let default = server
+            .for_workspace_argument(&serde_json::json!({}))
+            .unwrap();
+        assert_eq!(default.meta_dir, Some(platform));
+
+        let err = server
+            .for_workspace_argument(&serde_json::json!({"workspace": "nope"}))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("platform, product"));
+    }
+
+    #[test]
+    fn test_list_workspaces() {
+        let tmp = tempfile::tempdir().unwrap();
+        let platform = workspace(tmp.path(), "platform", r#"{"infra": "x"}"#);
+        let product = workspace(tmp.path(), "product", r#"{"app": "y", "web": "z"}"#);
+        let server = McpServer::with_workspaces(
+            vec![platform, product],
+            Outgoing::new(Box::new(std::io::sink())),
+        );
+
+        let listed = server
+            .tool_list_workspaces(&serde_json::json!({}))
+            .unwrap()
+            .into_value();
+        assert_eq!(listed[0]["name"], "platform");
+        assert_eq!(listed[0]["default"], true);
+        assert_eq!(listed[1]["name"], "product");
+        assert_eq!(listed[1]["projects"], 2);
+        assert_eq!(listed[1]["source"], "configured");
+    }
+}